@@ -0,0 +1,176 @@
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, Rgba, RgbaImage};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use crate::error::Result;
+use crate::grid;
+use crate::maze_logic::{Generator, Maze};
+
+const BACKGROUND: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const WALL: Rgba<u8> = Rgba([20, 20, 20, 255]);
+const UNVISITED: Rgba<u8> = Rgba([150, 150, 150, 255]);
+const CARVED: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const CURRENT: Rgba<u8> = Rgba([230, 60, 60, 255]);
+const REACHED: Rgba<u8> = Rgba([190, 215, 250, 255]);
+const FRONTIER: Rgba<u8> = Rgba([250, 160, 40, 255]);
+const PATH: Rgba<u8> = Rgba([60, 110, 230, 255]);
+const START: Rgba<u8> = Rgba([40, 160, 80, 255]);
+const END: Rgba<u8> = Rgba([220, 50, 50, 255]);
+// NeuQuant speed, 1 is best and slowest, 30 fastest
+const GIF_SPEED: i32 = 10;
+
+#[derive(Clone, Debug)]
+pub struct GifOptions {
+    /// side of a cell in pixels, walls included
+    pub cell_size: u32,
+    pub wall_width: u32,
+    pub frame_delay_ms: u32,
+    /// how long the finished maze stays up before the animation loops
+    pub final_delay_ms: u32,
+    /// carved walls or BFS layers per frame, raise it for big mazes
+    pub steps_per_frame: usize,
+}
+impl Default for GifOptions {
+    fn default() -> Self {
+        GifOptions { cell_size: 16, wall_width: 2, frame_delay_ms: 40, final_delay_ms: 2000, steps_per_frame: 1 }
+    }
+}
+
+impl Maze {
+    /// Cells reached by a breadth first search from start_pos, one layer per
+    /// distance. The search stops at the layer that contains end_pos.
+    pub fn bfs_layers(&self) -> Result<Vec<Vec<(u8, u8)>>> {
+        self.check_pos(self.start_pos)?;
+        self.check_pos(self.end_pos)?;
+        Ok(grid::bfs_layers(self, self.start_pos, self.end_pos))
+    }
+}
+
+// draws `maze` with every cell filled with its colour from `fills`
+fn draw(maze: &Maze, fills: &[Rgba<u8>], options: &GifOptions) -> RgbaImage {
+    let cell = options.cell_size;
+    let wall = options.wall_width.min(cell);
+    let width = maze.width as u32 * cell + wall;
+    let height = maze.height as u32 * cell + wall;
+    let mut image = RgbaImage::from_pixel(width, height, BACKGROUND);
+    let mut fill = |x0: u32, y0: u32, w: u32, h: u32, color: Rgba<u8>| {
+        for y in y0..(y0 + h).min(height) {
+            for x in x0..(x0 + w).min(width) {
+                image.put_pixel(x, y, color);
+            }
+        }
+    };
+
+    for y in 0..maze.height {
+        for x in 0..maze.width {
+            let (left, top) = (x as u32 * cell, y as u32 * cell);
+            fill(left + wall, top + wall, cell - wall, cell - wall, fills[y * maze.width + x]);
+            let i = y * maze.width + x;
+            if maze.r_walls[i] {
+                fill(left + cell, top, wall, cell + wall, WALL);
+            } else {
+                // the gap through the opened wall takes the cell's colour
+                fill(left + cell, top + wall, wall, cell - wall, fills[i]);
+            }
+            if maze.b_walls[i] {
+                fill(left, top + cell, cell + wall, wall, WALL);
+            } else {
+                fill(left + wall, top + cell, cell - wall, wall, fills[i]);
+            }
+        }
+    }
+    fill(0, 0, width, wall, WALL);
+    fill(0, 0, wall, height, WALL);
+    image
+}
+
+fn write_gif(path: &Path, frames: Vec<(RgbaImage, u32)>) -> Result<()> {
+    let mut encoder = GifEncoder::new_with_speed(BufWriter::new(File::create(path)?), GIF_SPEED);
+    encoder.set_repeat(Repeat::Infinite)?;
+    encoder.encode_frames(frames.into_iter().map(|(image, delay_ms)| {
+        Frame::from_parts(image, 0, 0, Delay::from_numer_denom_ms(delay_ms, 1))
+    }))?;
+    Ok(())
+}
+
+fn mark_ends(maze: &Maze, fills: &mut [Rgba<u8>]) {
+    fills[maze.start_pos.1 as usize * maze.width + maze.start_pos.0 as usize] = START;
+    fills[maze.end_pos.1 as usize * maze.width + maze.end_pos.0 as usize] = END;
+}
+
+/// Animates recursive backtracking carving a width x height maze, one frame
+/// per removed wall, and returns the maze. The maze is the one
+/// Maze::generate_with gives for a StdRng seeded with `seed`.
+pub fn generation_gif(width: usize, height: usize, seed: u64, path: &Path, options: &GifOptions) -> Result<Maze> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut maze = Maze::blank(width, height, Generator::RecursiveBacktrack)?;
+    maze.set_pos_with(&mut rng)?;
+    let mut carves = vec![];
+    maze.gen_maze_traced(&mut rng, &mut |x, y, direction| carves.push((x, y, direction)));
+
+    // replay the carving on a copy with every wall still standing
+    let mut state = Maze::blank(width, height, Generator::RecursiveBacktrack)?;
+    let mut fills = vec![UNVISITED; width * height];
+    let mut frames = vec![];
+    for (step, chunk) in carves.chunks(options.steps_per_frame.max(1)).enumerate() {
+        let mut current = vec![];
+        for &(x, y, direction) in chunk {
+            state.remove_wall(x, y, direction);
+            let (dx, dy) = direction.to_offset();
+            let next = ((x as i16 + dx) as usize, (y as i16 + dy) as usize);
+            if step == 0 {
+                fills[y * width + x] = CARVED;
+            }
+            fills[next.1 * width + next.0] = CARVED;
+            current.push(next.1 * width + next.0);
+        }
+        let mut frame_fills = fills.clone();
+        for i in current {
+            frame_fills[i] = CURRENT;
+        }
+        frames.push((draw(&state, &frame_fills, options), options.frame_delay_ms));
+    }
+
+    let mut final_fills = vec![CARVED; width * height];
+    mark_ends(&maze, &mut final_fills);
+    frames.push((draw(&maze, &final_fills, options), options.final_delay_ms));
+    write_gif(path, frames)?;
+    Ok(maze)
+}
+
+/// Animates a breadth first search over `maze` layer by layer, showing the
+/// frontier, the cells reached so far and finally the shortest path
+pub fn solving_gif(maze: &Maze, path: &Path, options: &GifOptions) -> Result<()> {
+    let layers = maze.bfs_layers()?;
+    let mut fills = vec![CARVED; maze.width * maze.height];
+    let mut frames = vec![];
+    for chunk in layers.chunks(options.steps_per_frame.max(1)) {
+        let mut frame_fills = fills.clone();
+        for (i, layer) in chunk.iter().enumerate() {
+            let frontier = i == chunk.len() - 1;
+            for &(x, y) in layer {
+                let cell = y as usize * maze.width + x as usize;
+                fills[cell] = REACHED;
+                frame_fills[cell] = if frontier { FRONTIER } else { REACHED };
+            }
+        }
+        mark_ends(maze, &mut frame_fills);
+        frames.push((draw(maze, &frame_fills, options), options.frame_delay_ms));
+    }
+
+    let mut solved = maze.clone();
+    if solved.bfs_solve().is_ok() {
+        let mut pos = solved.start_pos;
+        for &(dx, dy) in &solved.ideal_path {
+            pos = ((pos.0 as i16 + dx as i16) as u8, (pos.1 as i16 + dy as i16) as u8);
+            fills[pos.1 as usize * maze.width + pos.0 as usize] = PATH;
+        }
+    }
+    mark_ends(maze, &mut fills);
+    frames.push((draw(maze, &fills, options), options.final_delay_ms));
+    write_gif(path, frames)
+}
@@ -0,0 +1,88 @@
+use flate2::write::GzEncoder;
+use serde::{Serialize, Deserialize};
+use std::io::{self, Write};
+use std::path::Path;
+
+const ZSTD_LEVEL: i32 = 3;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+impl Compression {
+    /// picks the compression from a `.gz` or `.zst` extension, anything else is plain
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    /// `requested`, or whatever the extension of `path` asks for when None
+    pub fn resolve(requested: Option<Compression>, path: &str) -> Self {
+        requested.unwrap_or_else(|| Compression::from_path(path))
+    }
+}
+
+/// Writer that compresses on the way through. Unlike dropping the encoders,
+/// finish reports errors writing the gzip trailer or the last zstd frame.
+///
+/// Both formats allow several compressed streams to be concatenated, which
+/// is what lets mt_make_dataset compress each thread's output separately.
+pub enum CompressedWriter<W: Write> {
+    Plain(W),
+    Gzip(GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+}
+impl<W: Write> CompressedWriter<W> {
+    pub fn new(inner: W, compression: Compression) -> io::Result<Self> {
+        CompressedWriter::with_threads(inner, compression, 0)
+    }
+
+    /// `threads` extra worker threads for zstd, gzip always compresses on the
+    /// calling thread
+    pub fn with_threads(inner: W, compression: Compression, threads: u32) -> io::Result<Self> {
+        Ok(match compression {
+            Compression::None => CompressedWriter::Plain(inner),
+            Compression::Gzip => CompressedWriter::Gzip(GzEncoder::new(inner, flate2::Compression::default())),
+            Compression::Zstd => {
+                let mut encoder = zstd::Encoder::new(inner, ZSTD_LEVEL)?;
+                if threads > 0 {
+                    encoder.multithread(threads)?;
+                }
+                CompressedWriter::Zstd(encoder)
+            }
+        })
+    }
+
+    /// writes any trailer and returns the inner writer, flushed
+    pub fn finish(self) -> io::Result<W> {
+        let mut inner = match self {
+            CompressedWriter::Plain(inner) => inner,
+            CompressedWriter::Gzip(encoder) => encoder.finish()?,
+            CompressedWriter::Zstd(encoder) => encoder.finish()?,
+        };
+        inner.flush()?;
+        Ok(inner)
+    }
+}
+impl<W: Write> Write for CompressedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            CompressedWriter::Plain(inner) => inner.write(buf),
+            CompressedWriter::Gzip(encoder) => encoder.write(buf),
+            CompressedWriter::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            CompressedWriter::Plain(inner) => inner.flush(),
+            CompressedWriter::Gzip(encoder) => encoder.flush(),
+            CompressedWriter::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
@@ -1,5 +1,3 @@
-use const_random::const_random;
-
 pub const WIDTH: usize = 10;
 pub const HEIGHT: usize = 10;
 
@@ -0,0 +1,665 @@
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+use std::io::{Read, Write, BufWriter};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::sync_channel;
+use std::sync::{Arc, Mutex};
+use std::fs::File;
+use std::thread;
+
+use crate::error::{MazeError, Result};
+use crate::maze_logic::*;
+use crate::manifest::*;
+use crate::compression::*;
+
+// how many rejected mazes in a row a generator accepts before giving up, so
+// small maze sizes with only a handful of distinct layouts can't hang a run
+const MAX_REJECT_STREAK: usize = 10_000;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Dedup {
+    Off,
+    /// reject mazes whose wall layout has already been emitted
+    Layout,
+    /// reject mazes whose layout, start_pos and end_pos have already been emitted
+    Task,
+}
+
+/// Inclusive range of maze dimensions. Must allow at least two cells, since a
+/// maze needs distinct start and end positions.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SizeRange {
+    pub min_width: usize,
+    pub max_width: usize,
+    pub min_height: usize,
+    pub max_height: usize,
+}
+impl SizeRange {
+    pub fn fixed(width: usize, height: usize) -> Self {
+        SizeRange { min_width: width, max_width: width, min_height: height, max_height: height }
+    }
+
+    pub fn contains(&self, width: usize, height: usize) -> bool {
+        (self.min_width..=self.max_width).contains(&width)
+        && (self.min_height..=self.max_height).contains(&height)
+    }
+
+    fn sample<R: Rng>(&self, rng: &mut R) -> (usize, usize) {
+        loop {
+            let width = rng.gen_range(self.min_width..=self.max_width);
+            let height = rng.gen_range(self.min_height..=self.max_height);
+            if width * height > 1 {
+                return (width, height);
+            }
+        }
+    }
+}
+
+/// What gets generated, shared by every dataset writer
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GenConfig {
+    pub sizes: SizeRange,
+    /// each maze picks one of these at random
+    pub generators: Vec<Generator>,
+    pub dedup: Dedup,
+    /// dedup modulo rotations and reflections
+    pub symmetric: bool,
+    /// also emit the 7 rotations and reflections of every generated maze.
+    /// dedup only looks at the generated maze, copies follow it
+    pub augment: bool,
+}
+impl Default for GenConfig {
+    fn default() -> Self {
+        GenConfig {
+            sizes: SizeRange::fixed(9, 9),
+            generators: vec![Generator::RecursiveBacktrack],
+            dedup: Dedup::Off,
+            symmetric: false,
+            augment: false,
+        }
+    }
+}
+
+/// Set of canonical hashes shared by every thread of a run.
+pub struct DedupFilter {
+    mode: Dedup,
+    symmetric: bool,
+    seen: Mutex<HashSet<u64>>,
+}
+impl DedupFilter {
+    pub fn new(mode: Dedup, symmetric: bool) -> Self {
+        DedupFilter {
+            mode,
+            symmetric,
+            seen: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// returns true if the maze has not been seen before and records it
+    pub fn insert(&self, maze: &Maze) -> bool {
+        let hash = match self.mode {
+            Dedup::Off => return true,
+            Dedup::Layout => maze.layout_hash(self.symmetric),
+            Dedup::Task => maze.task_hash(self.symmetric),
+        };
+        self.seen.lock().unwrap().insert(hash)
+    }
+}
+
+// generates a solved maze, returning the generator that made it
+pub(crate) fn gen_maze<R: Rng>(config: &GenConfig, rng: &mut R) -> Result<(Maze, Generator)> {
+    let (width, height) = config.sizes.sample(rng);
+    let generator = *config.generators
+        .choose(rng)
+        .ok_or_else(|| MazeError::InvalidConfig("no generators configured".to_string()))?;
+    let mut maze = Maze::generate_with(width, height, generator, rng)?;
+    maze.bfs_solve()?;
+    Ok((maze, generator))
+}
+
+// generates a solved maze the filter has not seen yet, or None once
+// MAX_REJECT_STREAK duplicates came up in a row
+fn gen_unique(config: &GenConfig, filter: &DedupFilter) -> Result<Option<Maze>> {
+    let mut rng = thread_rng();
+    for _ in 0..MAX_REJECT_STREAK {
+        let (maze, _) = gen_maze(config, &mut rng)?;
+        if filter.insert(&maze) {
+            return Ok(Some(maze));
+        }
+    }
+    Ok(None)
+}
+
+// rng for the `attempt`th try at generating dataset record `index`
+pub(crate) fn index_rng(seed: u64, index: usize, attempt: usize) -> StdRng {
+    StdRng::seed_from_u64(mix64(mix64(mix64(seed) ^ index as u64) ^ attempt as u64))
+}
+
+// like gen_unique but fully determined by (seed, index) when dedup is off.
+// with dedup on, which of two duplicates survives depends on thread timing
+fn gen_indexed(config: &GenConfig, filter: &DedupFilter, seed: u64, index: usize) -> Result<Option<Maze>> {
+    for attempt in 0..MAX_REJECT_STREAK {
+        let (maze, _) = gen_maze(config, &mut index_rng(seed, index, attempt))?;
+        if filter.insert(&maze) {
+            return Ok(Some(maze));
+        }
+    }
+    Ok(None)
+}
+
+// the maze followed by its distinct rotations and reflections when augmenting
+fn expand(config: &GenConfig, maze: Maze) -> Vec<Maze> {
+    if !config.augment {
+        return vec![maze];
+    }
+    let mut seen = HashSet::new();
+    maze.augmentations()
+        .into_iter()
+        .filter(|copy| seen.insert(copy.task_hash(false)))
+        .collect()
+}
+
+/// `compression` of None picks it from the extension of `file_name`. Each
+/// thread serializes and compresses its own mazes, and the resulting gzip
+/// members or zstd frames are concatenated into the file in thread order.
+/// Returns the number of mazes written, less than `size` if dedup gave up.
+pub fn mt_make_dataset(file_name: &str, size: usize, config: &GenConfig, compression: Option<Compression>) -> Result<usize> {
+    let compression = Compression::resolve(compression, file_name);
+    let num_cpus = num_cpus::get();
+    let times = size / num_cpus;
+    let mut handles = vec![];
+
+    // Create a vector to store the mazes, each thread will append to it
+    let mut thread_times = vec![times; num_cpus];
+    thread_times[num_cpus - 1] += size - (times * num_cpus);
+
+    // One filter for the whole run so duplicates are caught across threads
+    let filter = Arc::new(DedupFilter::new(config.dedup, config.symmetric));
+
+    for times in thread_times {
+        let filter = Arc::clone(&filter);
+        let config = config.clone();
+
+        // Generate mazes, returning this thread's compressed lines
+        let handle = thread::spawn(move || -> Result<(Vec<u8>, usize)> {
+            let mut writer = CompressedWriter::new(Vec::new(), compression)?;
+            let mut written = 0;
+            while written < times {
+                let Some(maze) = gen_unique(&config, &filter)? else { break };
+                for copy in expand(&config, maze).into_iter().take(times - written) {
+                    writeln!(writer, "{}", serde_json::to_string(&copy)?)?;
+                    written += 1;
+                }
+            }
+            Ok((writer.finish()?, written))
+        });
+        handles.push(handle);
+    }
+
+    // Write each thread's output to the file
+    let file = File::create(file_name)?;
+    let mut writer = BufWriter::new(file);
+    let mut written = 0;
+    for handle in handles {
+        let (lines, count) = join(handle)?;
+        writer.write_all(&lines)?;
+        written += count;
+    }
+    writer.flush()?;
+    Ok(written)
+}
+
+// waits for a worker, turning a panic into an error
+fn join<T>(handle: thread::JoinHandle<Result<T>>) -> Result<T> {
+    handle.join().map_err(|_| MazeError::WorkerPanicked)?
+}
+
+/// `compression` of None picks it from the extension of `file_name`. zstd
+/// compresses on all cpus, gzip on this thread. Returns the number of mazes
+/// written, less than `size` if dedup gave up.
+pub fn make_dataset(file_name: &str, size: usize, config: &GenConfig, compression: Option<Compression>) -> Result<usize> {
+    let compression = Compression::resolve(compression, file_name);
+    let file = File::create(file_name)?;
+    let mut writer = CompressedWriter::with_threads(BufWriter::new(file), compression, num_cpus::get() as u32)?;
+    let filter = DedupFilter::new(config.dedup, config.symmetric);
+
+    let mut written = 0;
+    while written < size {
+        let Some(maze) = gen_unique(config, &filter)? else { break };
+        for copy in expand(config, maze).into_iter().take(size - written) {
+            let line = serde_json::to_string(&copy)?;
+            writeln!(writer, "{}", line)?;
+            written += 1;
+        }
+    }
+    writer.finish()?;
+    Ok(written)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Split {
+    Train,
+    Val,
+    Test,
+}
+impl Split {
+    pub const ALL: [Split; 3] = [Split::Train, Split::Val, Split::Test];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Split::Train => "train",
+            Split::Val => "val",
+            Split::Test => "test",
+        }
+    }
+}
+
+/// How mazes are divided between train, val and test.
+///
+/// A maze's split is a pure function of its canonical layout hash, so the same
+/// layout always lands in the same split no matter which thread or run made it.
+/// When any holdout is set the test split becomes out of distribution: it only
+/// receives held out mazes, and everything else is divided between train and val.
+#[derive(Clone, Debug)]
+pub struct SplitConfig {
+    /// relative sizes of train, val and test, they don't need to sum to 1
+    pub ratios: [f64; 3],
+    /// hash layouts modulo rotations and reflections, so a rotated copy of a
+    /// training maze can't show up in test
+    pub symmetric: bool,
+    pub holdout_sizes: Vec<SizeRange>,
+    pub holdout_generators: Vec<Generator>,
+}
+impl Default for SplitConfig {
+    fn default() -> Self {
+        SplitConfig {
+            ratios: [0.8, 0.1, 0.1],
+            symmetric: true,
+            holdout_sizes: vec![],
+            holdout_generators: vec![],
+        }
+    }
+}
+impl SplitConfig {
+    fn has_holdout(&self) -> bool {
+        !self.holdout_sizes.is_empty() || !self.holdout_generators.is_empty()
+    }
+
+    fn is_held_out(&self, maze: &Maze, generator: Generator) -> bool {
+        self.holdout_sizes.iter().any(|range| range.contains(maze.width, maze.height))
+        || self.holdout_generators.contains(&generator)
+    }
+
+    pub fn assign(&self, maze: &Maze, generator: Generator) -> Split {
+        if self.is_held_out(maze, generator) {
+            return Split::Test;
+        }
+
+        let [train, val, test] = self.ratios;
+        let test = if self.has_holdout() { 0.0 } else { test };
+        // the symmetric hash is the smallest of 8, so it's skewed towards 0 and
+        // has to be remixed before its top 53 bits work as a uniform value in [0, 1)
+        let u = (mix64(maze.layout_hash(self.symmetric)) >> 11) as f64 / (1u64 << 53) as f64;
+        let u = u * (train + val + test);
+
+        if u < train {
+            Split::Train
+        } else if u < train + val {
+            Split::Val
+        } else {
+            Split::Test
+        }
+    }
+
+    // the same config with every rotation and reflection of a maze assigned
+    // to one split: symmetric hashing, and holdout sizes matched both ways
+    // round since a rotation swaps width and height
+    fn closed_under_symmetry(&self) -> SplitConfig {
+        let mut holdout_sizes = self.holdout_sizes.clone();
+        for range in &self.holdout_sizes {
+            let transposed = SizeRange {
+                min_width: range.min_height,
+                max_width: range.max_height,
+                min_height: range.min_width,
+                max_height: range.max_width,
+            };
+            if !holdout_sizes.contains(&transposed) {
+                holdout_sizes.push(transposed);
+            }
+        }
+        SplitConfig { symmetric: true, holdout_sizes, ..self.clone() }
+    }
+
+    // number of mazes each split should get out of `size`
+    fn targets(&self, size: usize) -> [usize; 3] {
+        let total: f64 = self.ratios.iter().sum();
+        let train = (size as f64 * self.ratios[0] / total).round() as usize;
+        let val = ((size as f64 * self.ratios[1] / total).round() as usize).min(size - train);
+        [train, val, size - train - val]
+    }
+}
+
+// splitmix64 finalizer
+fn mix64(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+pub fn split_file_name(prefix: &str, split: Split) -> String {
+    format!("{}_{}.json", prefix, split.name())
+}
+
+/// Writes `size` mazes across `{prefix}_train.json`, `{prefix}_val.json` and
+/// `{prefix}_test.json`. Mazes are generated on every cpu and written by this
+/// thread, which drops any maze whose split is already full. Stops early if
+/// MAX_REJECT_STREAK mazes in a row are dropped, for example when a holdout
+/// matches nothing in `config.sizes`. Returns how many mazes each split got,
+/// indexed by `Split as usize`.
+///
+/// With `config.augment` on, splits are assigned as if `splits.symmetric`
+/// was set and every holdout size also held out its transpose, so no
+/// rotation or reflection of a layout can end up in two splits.
+pub fn make_splits(prefix: &str, size: usize, config: &GenConfig, splits: &SplitConfig) -> Result<[usize; 3]> {
+    let targets = splits.targets(size);
+    let splits = if config.augment { splits.closed_under_symmetry() } else { splits.clone() };
+    let mut counts = [0usize; 3];
+
+    let mut writers = Split::ALL
+        .iter()
+        .map(|&split| Ok(BufWriter::new(File::create(split_file_name(prefix, split))?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let filter = Arc::new(DedupFilter::new(config.dedup, config.symmetric));
+    let stop = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = sync_channel::<(Maze, Split)>(1024);
+    let mut handles = vec![];
+
+    for _ in 0..num_cpus::get() {
+        let config = config.clone();
+        let splits = splits.clone();
+        let filter = Arc::clone(&filter);
+        let stop = Arc::clone(&stop);
+        let sender = sender.clone();
+
+        let handle = thread::spawn(move || -> Result<()> {
+            let mut duplicates = 0;
+            while !stop.load(Ordering::Relaxed) && duplicates < MAX_REJECT_STREAK {
+                let (maze, generator) = gen_maze(&config, &mut thread_rng())?;
+                if !filter.insert(&maze) {
+                    duplicates += 1;
+                    continue;
+                }
+                duplicates = 0;
+                // augmented copies go wherever the generated maze goes
+                let split = splits.assign(&maze, generator);
+                for copy in expand(&config, maze) {
+                    if sender.send((copy, split)).is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+            Ok(())
+        });
+        handles.push(handle);
+    }
+    drop(sender);
+
+    let mut streak = 0;
+    for (maze, split) in receiver.iter() {
+        let i = split as usize;
+        if counts[i] < targets[i] {
+            writeln!(writers[i], "{}", serde_json::to_string(&maze)?)?;
+            counts[i] += 1;
+            streak = 0;
+        } else {
+            streak += 1;
+        }
+
+        if counts == targets || streak >= MAX_REJECT_STREAK {
+            break;
+        }
+    }
+
+    stop.store(true, Ordering::Relaxed);
+    drop(receiver);
+    for handle in handles {
+        join(handle)?;
+    }
+    for writer in writers.iter_mut() {
+        writer.flush()?;
+    }
+    Ok(counts)
+}
+
+/// Writes `size` mazes as `shard_size` record shards in `dir`, plus a
+/// manifest.json describing them. Record i is generated from (seed, i), and
+/// shard k holds records k * shard_size onwards. Each thread takes whole shards
+/// and streams them to disk, so finished shards are usable while the rest are
+/// still being written. Augmentation isn't supported here since it would break
+/// the mapping from records to indices.
+///
+/// The manifest is rewritten after every finished shard, so an interrupted run
+/// can be picked up again with resume_sharded.
+pub fn make_sharded(dir: &str, size: usize, shard_size: usize, seed: u64, config: &GenConfig) -> Result<Manifest> {
+    if shard_size == 0 {
+        return Err(MazeError::InvalidConfig("shard_size must be at least 1".to_string()));
+    }
+    std::fs::create_dir_all(dir)?;
+    let manifest = Manifest::new(seed, size, shard_size, config);
+    Ok(run_sharded(Path::new(dir), manifest)?.0)
+}
+
+/// What resume_sharded did with the shards an earlier run left behind
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct ResumeSummary {
+    /// finished shards kept as they were
+    pub kept: usize,
+    /// partially written shards whose complete records were kept
+    pub recovered: usize,
+    /// shards generated again from the start, because they were deleted or
+    /// didn't match their seed
+    pub restarted: usize,
+}
+
+/// Continues a make_sharded run from the manifest in `dir`. Shards the manifest
+/// lists as finished are kept if their size and sha256 still match, everything else is
+/// generated again, except that complete records at the start of a partially
+/// written shard are kept when the last of them matches its regenerated self.
+pub fn resume_sharded(dir: &str) -> Result<(Manifest, ResumeSummary)> {
+    let dir = Path::new(dir);
+    let mut manifest = Manifest::read(&dir.join(MANIFEST_FILE))?;
+    if manifest.complete {
+        let kept = manifest.shards.len();
+        return Ok((manifest, ResumeSummary { kept, ..ResumeSummary::default() }));
+    }
+
+    // a finished shard whose contents changed since is generated again. One
+    // that's gone has nothing for recover_shard to look at, so it's counted
+    // as restarted here
+    let mut deleted = 0;
+    manifest.shards.retain(|shard| match hash_file(&dir.join(&shard.file)) {
+        Ok((bytes, sha256)) => bytes == shard.bytes && sha256 == shard.sha256,
+        Err(_) => {
+            deleted += 1;
+            false
+        }
+    });
+    // add_shard counts the dropped shards again once they're rewritten
+    manifest.total_count = manifest.shards.iter().map(|shard| shard.count).sum();
+    let kept = manifest.shards.len();
+    let (manifest, summary) = run_sharded(dir, manifest)?;
+    Ok((manifest, ResumeSummary { kept, restarted: summary.restarted + deleted, ..summary }))
+}
+
+// Generates every shard `manifest` doesn't list yet, counting the partially
+// written ones it recovered or restarted
+fn run_sharded(dir: &Path, manifest: Manifest) -> Result<(Manifest, ResumeSummary)> {
+    let config = manifest.config.clone();
+    let filter = Arc::new(DedupFilter::new(config.dedup, config.symmetric));
+
+    // resumed runs have to remember what the finished shards already hold
+    if config.dedup != Dedup::Off {
+        for shard in &manifest.shards {
+            for maze in read_shard(&dir.join(&shard.file))? {
+                filter.insert(&maze);
+            }
+        }
+    }
+
+    let done: HashSet<usize> = manifest.shards.iter().map(|shard| shard.first_index / manifest.shard_size).collect();
+    let pending: Vec<usize> = (0..manifest.num_shards()).filter(|shard| !done.contains(shard)).collect();
+    let pending = Arc::new(pending);
+    let next = Arc::new(AtomicUsize::new(0));
+    let manifest = Arc::new(Mutex::new(manifest));
+    manifest.lock().unwrap().write(&dir.join(MANIFEST_FILE))?;
+    let mut handles = vec![];
+
+    for _ in 0..num_cpus::get().min(pending.len()) {
+        let dir = dir.to_path_buf();
+        let config = config.clone();
+        let pending = Arc::clone(&pending);
+        let next = Arc::clone(&next);
+        let filter = Arc::clone(&filter);
+        let manifest = Arc::clone(&manifest);
+
+        let handle = thread::spawn(move || -> Result<ResumeSummary> {
+            let mut summary = ResumeSummary::default();
+            let (seed, size, shard_size) = {
+                let manifest = manifest.lock().unwrap();
+                (manifest.seed, manifest.target_count, manifest.shard_size)
+            };
+            while let Some(&shard) = pending.get(next.fetch_add(1, Ordering::Relaxed)) {
+                let first_index = shard * shard_size;
+                let last_index = (first_index + shard_size).min(size);
+                let info = write_shard(&dir, shard, first_index..last_index, &config, &filter, seed, &mut summary)?;
+
+                let mut manifest = manifest.lock().unwrap();
+                manifest.add_shard(info);
+                manifest.write(&dir.join(MANIFEST_FILE))?;
+            }
+            Ok(summary)
+        });
+        handles.push(handle);
+    }
+
+    // every worker has to stop before the manifest can be taken back, so the
+    // first error is only reported once they all have
+    let results: Vec<_> = handles.into_iter().map(join).collect();
+    let summary = results.into_iter().try_fold(ResumeSummary::default(), |total, summary| {
+        let summary = summary?;
+        Ok::<_, MazeError>(ResumeSummary {
+            kept: total.kept + summary.kept,
+            recovered: total.recovered + summary.recovered,
+            restarted: total.restarted + summary.restarted,
+        })
+    })?;
+
+    let manifest = Arc::try_unwrap(manifest).map_err(|_| MazeError::WorkerPanicked)?;
+    let mut manifest = manifest.into_inner().map_err(|_| MazeError::WorkerPanicked)?;
+    manifest.complete = true;
+    manifest.write(&dir.join(MANIFEST_FILE))?;
+    Ok((manifest, summary))
+}
+
+fn write_shard(
+    dir: &Path,
+    shard: usize,
+    indices: std::ops::Range<usize>,
+    config: &GenConfig,
+    filter: &DedupFilter,
+    seed: u64,
+    summary: &mut ResumeSummary,
+) -> Result<ShardInfo> {
+    let file_name = shard_file_name(shard);
+    let path = dir.join(&file_name);
+    let first_index = indices.start;
+
+    // keep whatever an interrupted run already wrote, if it checks out
+    let (kept, kept_len) = recover_shard(&path, config, seed, first_index, summary)?.unwrap_or_default();
+    let mut count = kept.len();
+    for maze in &kept {
+        filter.insert(maze);
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)?;
+    file.set_len(kept_len as u64)?;
+    // leaves the file positioned at the end, ready to append
+    let mut prefix = Vec::with_capacity(kept_len);
+    file.read_to_end(&mut prefix)?;
+    let mut writer = HashingWriter::with_prefix(BufWriter::new(file), &prefix);
+
+    for index in (first_index + count)..indices.end {
+        let Some(maze) = gen_indexed(config, filter, seed, index)? else { break };
+        writeln!(writer, "{}", serde_json::to_string(&maze)?)?;
+        count += 1;
+    }
+
+    let (bytes, sha256) = writer.finish()?;
+    Ok(ShardInfo { file: file_name, first_index, count, bytes, sha256 })
+}
+
+// Complete records at the start of a partially written shard and their
+// length in bytes. A torn last line or a line that doesn't parse ends the
+// prefix. The last kept record must equal a fresh (seed, index) maze,
+// otherwise the shard was written with a different seed or config and nothing
+// is kept. Only the first attempt is regenerated, so with dedup on a record
+// that needed retries isn't recognised and the shard starts over. Either
+// outcome is counted in `summary`.
+fn recover_shard(
+    path: &Path,
+    config: &GenConfig,
+    seed: u64,
+    first_index: usize,
+    summary: &mut ResumeSummary,
+) -> Result<Option<(Vec<Maze>, usize)>> {
+    // a shard that was never started has nothing to recover
+    let Ok((mazes, len)) = read_shard_prefix(path) else { return Ok(None) };
+    let Some(last) = mazes.last() else {
+        // something was written but not one record of it is usable
+        if std::fs::metadata(path).is_ok_and(|meta| meta.len() > 0) {
+            summary.restarted += 1;
+        }
+        return Ok(None);
+    };
+
+    let index = first_index + mazes.len() - 1;
+    let (expected, _) = gen_maze(config, &mut index_rng(seed, index, 0))?;
+    if serde_json::to_string(&expected)? != serde_json::to_string(last)? {
+        summary.restarted += 1;
+        return Ok(None);
+    }
+    summary.recovered += 1;
+    Ok(Some((mazes, len)))
+}
+
+fn read_shard(path: &Path) -> std::io::Result<Vec<Maze>> {
+    Ok(read_shard_prefix(path)?.0)
+}
+
+// the complete lines of a shard that parse as mazes, stopping at the first
+// one that doesn't, and how many bytes they take up
+fn read_shard_prefix(path: &Path) -> std::io::Result<(Vec<Maze>, usize)> {
+    let content = std::fs::read(path)?;
+    let mut mazes = vec![];
+    let mut len = 0;
+    // the last piece is either empty or a line that was cut off
+    let mut lines: Vec<&[u8]> = content.split(|&byte| byte == b'\n').collect();
+    lines.pop();
+    for line in lines {
+        match serde_json::from_slice::<Maze>(line) {
+            Ok(maze) => mazes.push(maze),
+            Err(_) => break,
+        }
+        len += line.len() + 1;
+    }
+    Ok((mazes, len))
+}
@@ -0,0 +1,248 @@
+use serde::{Serialize, Deserialize};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::error::{MazeError, Result};
+use crate::maze_logic::{Direction, Maze};
+
+// probabilities are clamped to this so -ln p stays finite
+const MIN_PROBABILITY: f64 = 1e-9;
+// what repair_moves believes about cells the predicted moves do and don't pass through
+const ON_MOVES: f32 = 0.9;
+const OFF_MOVES: f32 = 0.1;
+
+/// A valid start to end path recovered from a prediction
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DecodedPath {
+    pub directions: Vec<Direction>,
+    /// (x, y) of every cell on the path, start_pos and end_pos included
+    pub cells: Vec<(u8, u8)>,
+    /// sum of ln p over `cells`
+    pub log_probability: f64,
+}
+impl DecodedPath {
+    /// the path as (dx, dy) steps, the format of Maze::ideal_path
+    pub fn offsets(&self) -> Vec<(i8, i8)> {
+        self.directions
+            .iter()
+            .map(|direction| {
+                let (dx, dy) = direction.to_offset();
+                (dx as i8, dy as i8)
+            })
+            .collect()
+    }
+}
+
+/// How far a path mask was from the path decoded out of it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MaskRepair {
+    pub path: DecodedPath,
+    /// path cells the mask left out
+    pub cells_added: usize,
+    /// mask cells that aren't on the path
+    pub cells_removed: usize,
+}
+impl MaskRepair {
+    pub fn edits(&self) -> usize {
+        self.cells_added + self.cells_removed
+    }
+
+    /// true if the mask already was exactly a valid path
+    pub fn was_valid(&self) -> bool {
+        self.edits() == 0
+    }
+}
+
+/// How far a predicted move sequence was from a valid path
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MoveRepair {
+    pub path: DecodedPath,
+    /// insertions, deletions and substitutions turning the predicted moves
+    /// into path.directions
+    pub edits: usize,
+}
+impl MoveRepair {
+    pub fn was_valid(&self) -> bool {
+        self.edits == 0
+    }
+}
+
+// heap entry, BinaryHeap is a max heap so the order is reversed
+struct Frontier {
+    cost: f64,
+    cell: usize,
+}
+impl PartialEq for Frontier {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Frontier {}
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost).then_with(|| other.cell.cmp(&self.cell))
+    }
+}
+
+/// Finds the start_pos to end_pos path through open passages that maximises
+/// the product of `probabilities` over its cells, with Dijkstra on -ln p.
+/// `probabilities` holds one value per cell, row major like the wall vectors.
+pub fn decode_path(maze: &Maze, probabilities: &[f32]) -> Result<DecodedPath> {
+    let cells = maze.width * maze.height;
+    if probabilities.len() != cells {
+        return Err(MazeError::ShapeMismatch { expected: cells, got: probabilities.len() });
+    }
+    maze.check_pos(maze.start_pos)?;
+    maze.check_pos(maze.end_pos)?;
+
+    let index = |pos: (u8, u8)| pos.1 as usize * maze.width + pos.0 as usize;
+    let position = |cell: usize| ((cell % maze.width) as u8, (cell / maze.width) as u8);
+    let cost = |cell: usize| -(probabilities[cell] as f64).clamp(MIN_PROBABILITY, 1.0).ln();
+
+    let start = index(maze.start_pos);
+    let end = index(maze.end_pos);
+    let mut best = vec![f64::INFINITY; cells];
+    let mut came_from: Vec<Option<(usize, Direction)>> = vec![None; cells];
+    let mut heap = BinaryHeap::from([Frontier { cost: cost(start), cell: start }]);
+    best[start] = cost(start);
+
+    while let Some(Frontier { cost: so_far, cell }) = heap.pop() {
+        if cell == end {
+            break;
+        }
+        if so_far > best[cell] {
+            continue;
+        }
+        for direction in Direction::ALL {
+            let Some(next) = maze.neighbour(position(cell), direction) else { continue };
+            let next = index(next);
+            let next_cost = so_far + cost(next);
+            if next_cost < best[next] {
+                best[next] = next_cost;
+                came_from[next] = Some((cell, direction));
+                heap.push(Frontier { cost: next_cost, cell: next });
+            }
+        }
+    }
+
+    if best[end].is_infinite() {
+        return Err(MazeError::Unsolvable);
+    }
+    let mut directions = vec![];
+    let mut path_cells = vec![maze.end_pos];
+    let mut cell = end;
+    while let Some((previous, direction)) = came_from[cell] {
+        directions.push(direction);
+        path_cells.push(position(previous));
+        cell = previous;
+    }
+    directions.reverse();
+    path_cells.reverse();
+    Ok(DecodedPath { directions, cells: path_cells, log_probability: -best[end] })
+}
+
+/// Decodes a predicted path mask, counting the cells at or above `threshold`
+/// as the raw prediction the edits are measured against
+pub fn repair_mask(maze: &Maze, probabilities: &[f32], threshold: f32) -> Result<MaskRepair> {
+    let path = decode_path(maze, probabilities)?;
+    let mut on_path = vec![false; probabilities.len()];
+    for &(x, y) in &path.cells {
+        on_path[y as usize * maze.width + x as usize] = true;
+    }
+
+    let mut cells_added = 0;
+    let mut cells_removed = 0;
+    for (&probability, &on_path) in probabilities.iter().zip(&on_path) {
+        match (probability >= threshold, on_path) {
+            (false, true) => cells_added += 1,
+            (true, false) => cells_removed += 1,
+            _ => {}
+        }
+    }
+    Ok(MaskRepair { path, cells_added, cells_removed })
+}
+
+/// Decodes a predicted move sequence. The moves are traced from start_pos
+/// ignoring walls, and the most probable valid path is the one that stays
+/// on the traced cells the most.
+pub fn repair_moves(maze: &Maze, moves: &[Direction]) -> Result<MoveRepair> {
+    maze.check_pos(maze.start_pos)?;
+    maze.check_pos(maze.end_pos)?;
+    let mut probabilities = vec![OFF_MOVES; maze.width * maze.height];
+    let (mut x, mut y) = (maze.start_pos.0 as i16, maze.start_pos.1 as i16);
+    probabilities[y as usize * maze.width + x as usize] = ON_MOVES;
+    for direction in moves {
+        let (dx, dy) = direction.to_offset();
+        x = (x + dx).clamp(0, maze.width as i16 - 1);
+        y = (y + dy).clamp(0, maze.height as i16 - 1);
+        probabilities[y as usize * maze.width + x as usize] = ON_MOVES;
+    }
+
+    let path = decode_path(maze, &probabilities)?;
+    let edits = edit_distance(moves, &path.directions);
+    Ok(MoveRepair { path, edits })
+}
+
+/// Levenshtein distance between two move sequences
+pub fn edit_distance(a: &[Direction], b: &[Direction]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, x) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + (x != y) as usize;
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::maze_logic::Generator;
+
+    fn solved() -> Maze {
+        let mut maze = Maze::generate_with(6, 6, Generator::RecursiveBacktrack, &mut StdRng::seed_from_u64(4)).unwrap();
+        maze.bfs_solve().unwrap();
+        maze
+    }
+
+    #[test]
+    fn repair_moves_rejects_positions_outside_the_maze() {
+        let mut maze = solved();
+        maze.start_pos = (6, 0);
+        assert!(matches!(repair_moves(&maze, &[Direction::Right]), Err(MazeError::OutOfRange { .. })));
+
+        let mut maze = solved();
+        maze.end_pos = (0, 200);
+        assert!(matches!(repair_moves(&maze, &[Direction::Right]), Err(MazeError::OutOfRange { .. })));
+    }
+
+    #[test]
+    fn repair_moves_keeps_a_valid_path() {
+        let maze = solved();
+        let moves = maze.ideal_directions();
+        let repair = repair_moves(&maze, &moves).unwrap();
+        assert!(repair.was_valid());
+        assert_eq!(repair.path.directions, moves);
+    }
+
+    #[test]
+    fn edit_distance_counts_single_edits() {
+        use Direction::*;
+        assert_eq!(edit_distance(&[Left, Up, Right], &[Left, Up, Right]), 0);
+        assert_eq!(edit_distance(&[Left, Up, Right], &[Left, Right]), 1);
+        assert_eq!(edit_distance(&[Left], &[Down]), 1);
+        assert_eq!(edit_distance(&[], &[Up, Up]), 2);
+    }
+}
@@ -0,0 +1,208 @@
+use std::path::Path;
+
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{MazeError, Result};
+use crate::grid::{self, Grid};
+use crate::maze_logic::{check_dimensions, Direction};
+use crate::svg::{grid_svg, Edge, SvgLayout, SvgOptions};
+
+/// A maze of triangles in width x height rows. Cell (x, y) points up when
+/// x + y is even and down otherwise, so it has neighbours to its left and
+/// right and a third one below it if it points up or above it if it points
+/// down. Moves reuse Direction, Up only works from a cell pointing down and
+/// Down only from one pointing up. Per-cell vectors are row major, index
+/// y * width + x.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DeltaMaze {
+    pub width: usize,
+    pub height: usize,
+    /// wall on the right side of each cell, always set on the last column
+    pub r_walls: Vec<bool>,
+    /// wall on the flat bottom of cells pointing up, always set on the last
+    /// row. Unused for cells pointing down, their flat side is the b_wall of
+    /// the cell above
+    pub b_walls: Vec<bool>,
+    /// shortest path from start_pos to end_pos, filled by bfs_solve
+    pub ideal_path: Vec<Direction>,
+    /// (x, y)
+    pub start_pos: (u8, u8),
+    /// (x, y)
+    pub end_pos: (u8, u8),
+}
+impl DeltaMaze {
+    /// Generates a triangle maze with recursive backtracking and random,
+    /// distinct start and end positions. Same limits on the sides as Maze::new.
+    pub fn new(width: usize, height: usize) -> Result<Self> {
+        DeltaMaze::generate_with(width, height, &mut thread_rng())
+    }
+
+    /// same as new but draws everything from `rng`
+    pub fn generate_with<R: Rng>(width: usize, height: usize, rng: &mut R) -> Result<Self> {
+        check_dimensions(width, height)?;
+        let size = width * height;
+        let mut maze = DeltaMaze {
+            width,
+            height,
+            r_walls: vec![true; size],
+            b_walls: vec![true; size],
+            ideal_path: vec![],
+            start_pos: (0, 0),
+            end_pos: (0, 0),
+        };
+        loop {
+            let start = rng.gen_range(0..size);
+            let end = rng.gen_range(0..size);
+            if start != end {
+                maze.start_pos = maze.pos(start);
+                maze.end_pos = maze.pos(end);
+                break;
+            }
+        }
+        let first = maze.pos(rng.gen_range(0..size));
+        grid::recursive_backtrack(&mut maze, first, rng, &mut |_, _| {});
+        Ok(maze)
+    }
+
+    /// true if the cell at (x, y) points up, with its flat side at the bottom
+    pub fn points_up(&self, (x, y): (u8, u8)) -> bool {
+        (x as usize + y as usize).is_multiple_of(2)
+    }
+
+    /// OutOfRange unless `pos` is a cell of this maze
+    pub fn check_pos(&self, pos: (u8, u8)) -> Result<()> {
+        let pos = (pos.0 as usize, pos.1 as usize);
+        if pos.0 < self.width && pos.1 < self.height {
+            Ok(())
+        } else {
+            Err(MazeError::OutOfRange { pos, width: self.width, height: self.height })
+        }
+    }
+
+    /// Finds a shortest path from start_pos to end_pos, storing it in
+    /// ideal_path. Returns Unsolvable, leaving ideal_path alone, if there is none.
+    pub fn bfs_solve(&mut self) -> Result<Vec<Direction>> {
+        self.check_pos(self.start_pos)?;
+        self.check_pos(self.end_pos)?;
+        let path = grid::bfs_path(self, self.start_pos, self.end_pos).ok_or(MazeError::Unsolvable)?;
+        self.ideal_path = path.clone();
+        Ok(path)
+    }
+
+    /// true if ideal_path leads from start_pos to end_pos without crossing a wall
+    pub fn can_follow_path(&self) -> bool {
+        grid::follows_path(self, self.start_pos, self.end_pos, &self.ideal_path)
+    }
+
+    /// Renders the maze with grid_svg, cell_size is the side of a triangle
+    pub fn to_svg(&self, options: &SvgOptions) -> Result<String> {
+        grid_svg(self, self.start_pos, self.end_pos, &self.ideal_path, options)
+    }
+
+    pub fn write_svg(&self, path: &Path, options: &SvgOptions) -> Result<()> {
+        std::fs::write(path, self.to_svg(options)?)?;
+        Ok(())
+    }
+}
+
+impl Grid for DeltaMaze {
+    type Pos = (u8, u8);
+    type Dir = Direction;
+
+    fn cell_count(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn index(&self, pos: (u8, u8)) -> Option<usize> {
+        self.check_pos(pos).ok()?;
+        Some(pos.1 as usize * self.width + pos.0 as usize)
+    }
+
+    fn pos(&self, index: usize) -> (u8, u8) {
+        ((index % self.width) as u8, (index / self.width) as u8)
+    }
+
+    /// left and right, then the flat side
+    fn directions(&self, pos: (u8, u8)) -> Vec<Direction> {
+        let flat = if self.points_up(pos) { Direction::Down } else { Direction::Up };
+        vec![Direction::Left, Direction::Right, flat]
+    }
+
+    fn step(&self, pos: (u8, u8), direction: Direction) -> Option<(u8, u8)> {
+        self.index(pos)?;
+        let (x, y) = (pos.0 as usize, pos.1 as usize);
+        let next = match direction {
+            Direction::Left if x > 0 => (x - 1, y),
+            Direction::Right if x < self.width - 1 => (x + 1, y),
+            Direction::Up if y > 0 && !self.points_up(pos) => (x, y - 1),
+            Direction::Down if y < self.height - 1 && self.points_up(pos) => (x, y + 1),
+            _ => return None,
+        };
+        Some((next.0 as u8, next.1 as u8))
+    }
+
+    fn has_wall(&self, pos: (u8, u8), direction: Direction) -> bool {
+        let (Some(i), Some(_)) = (self.index(pos), self.step(pos, direction)) else {
+            return true;
+        };
+        match direction {
+            Direction::Left => self.r_walls[i - 1],
+            Direction::Right => self.r_walls[i],
+            Direction::Up => self.b_walls[i - self.width],
+            Direction::Down => self.b_walls[i],
+        }
+    }
+
+    fn set_wall(&mut self, pos: (u8, u8), direction: Direction, wall: bool) {
+        let (Some(i), Some(_)) = (self.index(pos), self.step(pos, direction)) else {
+            return;
+        };
+        match direction {
+            Direction::Left => self.r_walls[i - 1] = wall,
+            Direction::Right => self.r_walls[i] = wall,
+            Direction::Up => self.b_walls[i - self.width] = wall,
+            Direction::Down => self.b_walls[i] = wall,
+        }
+    }
+}
+
+impl SvgLayout for DeltaMaze {
+    fn svg_size(&self, cell_size: f32) -> (f32, f32) {
+        ((self.width as f32 + 1.0) * cell_size / 2.0, self.height as f32 * cell_size * 3f32.sqrt() / 2.0)
+    }
+
+    // the centroid, a third of the way from the flat side to the tip
+    fn svg_centre(&self, pos: (u8, u8), cell_size: f32) -> (f32, f32) {
+        let rise = cell_size * 3f32.sqrt() / 2.0;
+        let y = pos.1 as f32 + if self.points_up(pos) { 2.0 / 3.0 } else { 1.0 / 3.0 };
+        ((pos.0 as f32 + 1.0) * cell_size / 2.0, y * rise)
+    }
+
+    fn svg_sides(&self, pos: (u8, u8), cell_size: f32) -> Vec<(Direction, Edge)> {
+        let rise = cell_size * 3f32.sqrt() / 2.0;
+        let (x, y) = (pos.0 as f32, pos.1 as f32);
+        let (flat, tip) = if self.points_up(pos) { (y + 1.0, y) } else { (y, y + 1.0) };
+        let left = (x * cell_size / 2.0, flat * rise);
+        let right = ((x + 2.0) * cell_size / 2.0, flat * rise);
+        let tip = ((x + 1.0) * cell_size / 2.0, tip * rise);
+        let line = |from, to| Edge::Line { from, to };
+        if self.points_up(pos) {
+            vec![
+                (Direction::Right, line(tip, right)),
+                (Direction::Down, line(right, left)),
+                (Direction::Left, line(left, tip)),
+            ]
+        } else {
+            vec![
+                (Direction::Up, line(left, right)),
+                (Direction::Right, line(right, tip)),
+                (Direction::Left, line(tip, left)),
+            ]
+        }
+    }
+
+    fn svg_marker_radius(&self, cell_size: f32) -> f32 {
+        cell_size * 0.15
+    }
+}
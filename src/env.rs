@@ -0,0 +1,384 @@
+use ndarray::{s, Array3, ArrayViewMut3};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::time::Instant;
+
+use crate::dataset::{gen_maze, GenConfig};
+use crate::error::{MazeError, Result};
+use crate::maze_logic::{Direction, Maze};
+use crate::trajectory::{Agent, Ending, MazeSource, Trajectory};
+
+/// Observation channels, in order: a wall on the up, right, down and left
+/// side of the cell, the agent, the goal, cells outside the maze, cells the
+/// agent sees right now and cells it has seen at some point. Cells outside
+/// the maze have all four walls. Walls and the goal only show on cells that
+/// have been seen, see Visibility.
+pub const CHANNELS: usize = 9;
+const WALL_CHANNELS: [(usize, Direction); 4] = [
+    (0, Direction::Up),
+    (1, Direction::Right),
+    (2, Direction::Down),
+    (3, Direction::Left),
+];
+const AGENT: usize = 4;
+const GOAL: usize = 5;
+const OUTSIDE: usize = 6;
+const VISIBLE: usize = 7;
+const KNOWN: usize = 8;
+
+/// How much of the maze the agent sees
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ObservationKind {
+    /// the whole maze, CHANNELS x height x width
+    Full,
+    /// a size x size window centred on the agent, size has to be odd
+    Local { size: usize },
+}
+
+/// Which cells the agent sees from where it stands. Everything it has seen
+/// during an episode stays on the observation as a memory map.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Visibility {
+    /// the whole maze is known from the start
+    All,
+    /// cells within this euclidean distance, walls don't block the view
+    Radius(f32),
+    /// its own cell and the cells along each open corridor until a wall
+    LineOfSight,
+}
+
+/// Rewards per step. Penalties are subtracted, so they should be positive.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Rewards {
+    /// paid on every step, bumps and the final step included
+    pub step_penalty: f32,
+    /// paid on top of step_penalty when a move runs into a wall
+    pub wall_penalty: f32,
+    /// paid on top of step_penalty when the agent reaches end_pos
+    pub goal_reward: f32,
+}
+impl Default for Rewards {
+    fn default() -> Self {
+        Rewards { step_penalty: 0.01, wall_penalty: 0.1, goal_reward: 1.0 }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EnvConfig {
+    /// mazes to play on, dedup is ignored
+    pub mazes: GenConfig,
+    pub rewards: Rewards,
+    /// episodes are cut off after this many steps. None allows 4 steps per cell
+    pub max_steps: Option<usize>,
+    pub observation: ObservationKind,
+    pub visibility: Visibility,
+}
+impl Default for EnvConfig {
+    fn default() -> Self {
+        EnvConfig {
+            mazes: GenConfig::default(),
+            rewards: Rewards::default(),
+            max_steps: None,
+            observation: ObservationKind::Full,
+            visibility: Visibility::All,
+        }
+    }
+}
+impl EnvConfig {
+    /// (channels, rows, columns) big enough for an observation of any maze
+    /// this config generates, full observations are padded to the largest one
+    pub fn observation_shape(&self) -> (usize, usize, usize) {
+        match self.observation {
+            ObservationKind::Full => (CHANNELS, self.mazes.sizes.max_height, self.mazes.sizes.max_width),
+            ObservationKind::Local { size } => (CHANNELS, size, size),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Observation {
+    /// CHANNELS x rows x columns, see ObservationKind
+    pub grid: Array3<f32>,
+    /// (x, y) of the agent
+    pub position: (u8, u8),
+    /// (x, y) of end_pos
+    pub goal: (u8, u8),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct StepInfo {
+    /// the move ran into a wall and the agent stayed put
+    pub bumped_wall: bool,
+    pub reached_goal: bool,
+    /// the episode hit max_steps before reaching the goal
+    pub truncated: bool,
+    /// steps taken this episode, this one included
+    pub steps: usize,
+}
+
+/// A maze as a reinforcement learning environment. The agent starts on
+/// start_pos and has to reach end_pos, moving with the same wall rules as
+/// Maze::can_move. An episode ends on reaching the goal or after max_steps.
+pub struct MazeEnv {
+    config: EnvConfig,
+    maze: Option<Maze>,
+    position: (u8, u8),
+    steps: usize,
+    done: bool,
+    // per cell, row major
+    visible: Vec<bool>,
+    known: Vec<bool>,
+    // every action this episode and when it was taken
+    actions: Vec<Direction>,
+    times_ms: Vec<u64>,
+    started: Instant,
+}
+impl MazeEnv {
+    pub fn new(config: EnvConfig) -> Result<Self> {
+        if let ObservationKind::Local { size } = config.observation {
+            if size % 2 == 0 {
+                return Err(MazeError::InvalidConfig(format!("local observation size {} is not odd", size)));
+            }
+        }
+        Ok(MazeEnv {
+            config,
+            maze: None,
+            position: (0, 0),
+            steps: 0,
+            done: true,
+            visible: vec![],
+            known: vec![],
+            actions: vec![],
+            times_ms: vec![],
+            started: Instant::now(),
+        })
+    }
+
+    pub fn config(&self) -> &EnvConfig {
+        &self.config
+    }
+
+    /// Starts an episode on a fresh maze. The same seed always gives the same maze.
+    pub fn reset(&mut self, seed: u64) -> Result<Observation> {
+        self.start(seed)?;
+        Ok(self.observe())
+    }
+
+    /// Starts an episode on `maze`, for example one read from a dataset
+    pub fn reset_with_maze(&mut self, maze: Maze) -> Result<Observation> {
+        self.start_with_maze(maze)?;
+        Ok(self.observe())
+    }
+
+    // reset without building an observation
+    pub(crate) fn start(&mut self, seed: u64) -> Result<()> {
+        let (maze, _) = gen_maze(&self.config.mazes, &mut StdRng::seed_from_u64(seed))?;
+        self.start_with_maze(maze)
+    }
+
+    fn start_with_maze(&mut self, maze: Maze) -> Result<()> {
+        maze.check_pos(maze.start_pos)?;
+        maze.check_pos(maze.end_pos)?;
+        let cells = maze.width * maze.height;
+        self.position = maze.start_pos;
+        self.maze = Some(maze);
+        self.steps = 0;
+        self.done = false;
+        self.visible = vec![false; cells];
+        self.known = vec![false; cells];
+        self.actions.clear();
+        self.times_ms.clear();
+        self.started = Instant::now();
+        self.look();
+        Ok(())
+    }
+
+    // recomputes what the agent sees and adds it to what it knows
+    fn look(&mut self) {
+        let Some(maze) = &self.maze else { return };
+        let (x, y) = self.position;
+        match self.config.visibility {
+            Visibility::All => self.visible.fill(true),
+            Visibility::Radius(radius) => {
+                for (i, visible) in self.visible.iter_mut().enumerate() {
+                    let dx = (i % maze.width) as f32 - x as f32;
+                    let dy = (i / maze.width) as f32 - y as f32;
+                    *visible = dx * dx + dy * dy <= radius * radius;
+                }
+            }
+            Visibility::LineOfSight => {
+                self.visible.fill(false);
+                self.visible[y as usize * maze.width + x as usize] = true;
+                for direction in Direction::ALL {
+                    let mut pos = self.position;
+                    while let Some(next) = maze.neighbour(pos, direction) {
+                        self.visible[next.1 as usize * maze.width + next.0 as usize] = true;
+                        pos = next;
+                    }
+                }
+            }
+        }
+        for (known, &visible) in self.known.iter_mut().zip(&self.visible) {
+            *known |= visible;
+        }
+    }
+
+    /// Moves the agent, returning (observation, reward, done, info). Fails
+    /// with EpisodeOver before the first reset and once an episode is done.
+    pub fn step(&mut self, direction: Direction) -> Result<(Observation, f32, bool, StepInfo)> {
+        let (reward, done, info) = self.step_only(direction)?;
+        Ok((self.observe(), reward, done, info))
+    }
+
+    // step without building an observation, for callers that write it themselves
+    pub(crate) fn step_only(&mut self, direction: Direction) -> Result<(f32, bool, StepInfo)> {
+        let maze = match &self.maze {
+            Some(maze) if !self.done => maze,
+            _ => return Err(MazeError::EpisodeOver),
+        };
+        let rewards = self.config.rewards;
+        self.steps += 1;
+        self.actions.push(direction);
+        self.times_ms.push(self.started.elapsed().as_millis() as u64);
+
+        let mut info = StepInfo { steps: self.steps, ..StepInfo::default() };
+        let mut reward = -rewards.step_penalty;
+        match maze.neighbour(self.position, direction) {
+            Some(position) => self.position = position,
+            None => {
+                info.bumped_wall = true;
+                reward -= rewards.wall_penalty;
+            }
+        }
+
+        let reached_goal = self.position == maze.end_pos;
+        self.look();
+        if reached_goal {
+            info.reached_goal = true;
+            reward += rewards.goal_reward;
+        } else if self.steps >= self.max_steps() {
+            info.truncated = true;
+        }
+        self.done = info.reached_goal || info.truncated;
+        Ok((reward, self.done, info))
+    }
+
+    /// the maze of the current episode, None before the first reset
+    pub fn maze(&self) -> Option<&Maze> {
+        self.maze.as_ref()
+    }
+
+    /// (x, y) of the agent
+    pub fn position(&self) -> (u8, u8) {
+        self.position
+    }
+
+    /// per cell, row major, whether the agent sees it right now
+    pub fn visible(&self) -> &[bool] {
+        &self.visible
+    }
+
+    /// per cell, row major, whether the agent has seen it this episode. The
+    /// walls of these cells are the agent's map of the maze
+    pub fn known(&self) -> &[bool] {
+        &self.known
+    }
+
+    /// The episode so far as a trajectory by `agent`, with the maze embedded.
+    /// None before the first reset
+    pub fn trajectory(&self, agent: Agent) -> Option<Trajectory> {
+        let maze = self.maze.as_ref()?;
+        let ending = if self.position == maze.end_pos {
+            Ending::Solved
+        } else if self.done {
+            Ending::Truncated
+        } else {
+            Ending::Abandoned
+        };
+        Some(Trajectory {
+            agent,
+            maze: MazeSource::Embedded(maze.clone()),
+            actions: self.actions.clone(),
+            times_ms: self.times_ms.clone(),
+            ending,
+            hints: 0,
+        })
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    pub fn max_steps(&self) -> usize {
+        let cells = self.maze.as_ref().map_or(0, |maze| maze.width * maze.height);
+        self.config.max_steps.unwrap_or(4 * cells)
+    }
+
+    /// (channels, rows, columns) of the observations of the current episode
+    pub fn observation_shape(&self) -> (usize, usize, usize) {
+        match self.config.observation {
+            ObservationKind::Full => {
+                let (width, height) = self.maze.as_ref().map_or((0, 0), |maze| (maze.width, maze.height));
+                (CHANNELS, height, width)
+            }
+            ObservationKind::Local { size } => (CHANNELS, size, size),
+        }
+    }
+
+    pub fn observe(&self) -> Observation {
+        let (channels, rows, columns) = self.observation_shape();
+        let mut grid = Array3::zeros((channels, rows, columns));
+        self.write_observation(grid.view_mut());
+        let goal = self.maze.as_ref().map_or((0, 0), |maze| maze.end_pos);
+        Observation { grid, position: self.position, goal }
+    }
+
+    /// Writes the current observation into `out`, which has to have
+    /// observation_shape. A full observation can also go into a larger `out`,
+    /// the cells past the maze are marked outside. Everything in `out` is
+    /// overwritten.
+    pub fn write_observation(&self, mut out: ArrayViewMut3<f32>) {
+        out.fill(0.0);
+        let Some(maze) = &self.maze else { return };
+        let (_, rows, columns) = out.dim();
+        // maze coordinates of the top left corner of the window
+        let (left, top) = match self.config.observation {
+            ObservationKind::Full => (0, 0),
+            ObservationKind::Local { size } => {
+                let radius = (size / 2) as i32;
+                (self.position.0 as i32 - radius, self.position.1 as i32 - radius)
+            }
+        };
+
+        for row in 0..rows {
+            for column in 0..columns {
+                let (x, y) = (left + column as i32, top + row as i32);
+                let mut cell = out.slice_mut(s![.., row, column]);
+                if x < 0 || y < 0 || x >= maze.width as i32 || y >= maze.height as i32 {
+                    for (channel, _) in WALL_CHANNELS {
+                        cell[channel] = 1.0;
+                    }
+                    cell[OUTSIDE] = 1.0;
+                    continue;
+                }
+
+                let pos = (x as u8, y as u8);
+                let index = y as usize * maze.width + x as usize;
+                cell[AGENT] = (pos == self.position) as u8 as f32;
+                cell[VISIBLE] = self.visible[index] as u8 as f32;
+                if !self.known[index] {
+                    continue;
+                }
+                cell[KNOWN] = 1.0;
+                for (channel, direction) in WALL_CHANNELS {
+                    cell[channel] = !maze.can_move(pos, direction) as u8 as f32;
+                }
+                cell[GOAL] = (pos == maze.end_pos) as u8 as f32;
+            }
+        }
+    }
+}
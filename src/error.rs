@@ -0,0 +1,109 @@
+use std::fmt;
+use std::io;
+
+use crate::reader::ReadError;
+
+/// Everything that can go wrong in the library. The binary wraps these in
+/// anyhow, library code returns them.
+#[derive(Debug)]
+pub enum MazeError {
+    /// a maze needs at least two cells, and positions are stored as u8 so
+    /// neither side can exceed 256
+    InvalidDimensions { width: usize, height: usize },
+    /// an (x, y) position outside a width x height maze
+    OutOfRange { pos: (usize, usize), width: usize, height: usize },
+    /// there is no path from start_pos to end_pos
+    Unsolvable,
+    /// per-cell input, like a predicted probability map, whose length doesn't
+    /// match the number of cells
+    ShapeMismatch { expected: usize, got: usize },
+    /// a (channels, rows, columns) observation that doesn't fit a policy's
+    /// input, full observations can be padded but not cropped
+    ObservationShape { needed: (usize, usize, usize), policy: (usize, usize, usize) },
+    /// malformed ASCII art, with the 1-based line and column of the problem
+    Parse { line: usize, column: usize, message: String },
+    /// a dataset or generator setting that can't work, e.g. no generators
+    InvalidConfig(String),
+    Io(io::Error),
+    Serialization(serde_json::Error),
+    /// encoding an image or animation failed
+    Image(image::ImageError),
+    /// a dataset record that couldn't be read
+    Read(ReadError),
+    /// a dataset worker thread panicked
+    WorkerPanicked,
+    /// an ONNX policy that couldn't be loaded or run
+    Model(anyhow::Error),
+    /// MazeEnv::step was called before reset or after the episode ended
+    EpisodeOver,
+}
+impl fmt::Display for MazeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MazeError::InvalidDimensions { width, height } => {
+                write!(f, "invalid maze dimensions {}x{}", width, height)
+            }
+            MazeError::OutOfRange { pos, width, height } => {
+                write!(f, "position {:?} is outside a {}x{} maze", pos, width, height)
+            }
+            MazeError::Unsolvable => write!(f, "maze has no path from start to end"),
+            MazeError::ShapeMismatch { expected, got } => {
+                write!(f, "expected {} values, one per cell, got {}", expected, got)
+            }
+            MazeError::ObservationShape { needed, policy } => write!(
+                f,
+                "observation of {}x{}x{} doesn't fit a policy input of {}x{}x{}",
+                needed.0, needed.1, needed.2, policy.0, policy.1, policy.2
+            ),
+            MazeError::Parse { line, column, message } => write!(f, "{}:{}: {}", line, column, message),
+            MazeError::InvalidConfig(message) => write!(f, "invalid config: {}", message),
+            MazeError::Io(e) => write!(f, "{}", e),
+            MazeError::Serialization(e) => write!(f, "serialization failed: {}", e),
+            MazeError::Image(e) => write!(f, "image encoding failed: {}", e),
+            MazeError::Read(e) => write!(f, "{}", e),
+            MazeError::WorkerPanicked => write!(f, "a worker thread panicked"),
+            MazeError::Model(e) => write!(f, "model failed: {:#}", e),
+            MazeError::EpisodeOver => write!(f, "episode is over, call reset first"),
+        }
+    }
+}
+impl std::error::Error for MazeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MazeError::Io(e) => Some(e),
+            MazeError::Serialization(e) => Some(e),
+            MazeError::Image(e) => Some(e),
+            MazeError::Read(e) => Some(e),
+            MazeError::Model(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+impl From<io::Error> for MazeError {
+    fn from(e: io::Error) -> Self {
+        MazeError::Io(e)
+    }
+}
+impl From<serde_json::Error> for MazeError {
+    fn from(e: serde_json::Error) -> Self {
+        MazeError::Serialization(e)
+    }
+}
+impl From<image::ImageError> for MazeError {
+    fn from(e: image::ImageError) -> Self {
+        MazeError::Image(e)
+    }
+}
+// tract reports its errors as anyhow errors
+impl From<anyhow::Error> for MazeError {
+    fn from(e: anyhow::Error) -> Self {
+        MazeError::Model(e)
+    }
+}
+impl From<ReadError> for MazeError {
+    fn from(e: ReadError) -> Self {
+        MazeError::Read(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, MazeError>;
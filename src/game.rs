@@ -0,0 +1,255 @@
+use std::io::{self, Write};
+use std::time::Instant;
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::{cursor, execute, queue, terminal};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::error::Result;
+use crate::maze_logic::{Direction, Generator, Maze};
+use crate::render::RenderOptions;
+use crate::trajectory::{Agent, Ending, MazeSource, Trajectory};
+
+/// A human playing through a maze with the wall rules of Maze::can_move. The
+/// game knows nothing about terminals, play drives it from the keyboard.
+pub struct Game {
+    seed: u64,
+    maze: Maze,
+    position: (u8, u8),
+    moves: usize,
+    // per cell, row major, cells the player has stood on this attempt
+    visited: Vec<bool>,
+    attempt: Trajectory,
+    attempts: Vec<Trajectory>,
+    started: Instant,
+}
+impl Game {
+    /// A width x height recursive backtracking maze, the same one for the same seed
+    pub fn new(width: usize, height: usize, seed: u64) -> Result<Self> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut maze = Maze::generate_with(width, height, Generator::RecursiveBacktrack, &mut rng)?;
+        maze.bfs_solve()?;
+        let cells = maze.width * maze.height;
+        let mut game = Game {
+            seed,
+            position: maze.start_pos,
+            maze,
+            moves: 0,
+            visited: vec![false; cells],
+            attempt: Trajectory {
+                agent: Agent::Human,
+                maze: MazeSource::Seeded { width, height, generator: Generator::RecursiveBacktrack, seed },
+                actions: vec![],
+                times_ms: vec![],
+                ending: Ending::Abandoned,
+                hints: 0,
+            },
+            attempts: vec![],
+            started: Instant::now(),
+        };
+        game.restart();
+        Ok(game)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn maze(&self) -> &Maze {
+        &self.maze
+    }
+
+    /// (x, y) of the player
+    pub fn position(&self) -> (u8, u8) {
+        self.position
+    }
+
+    /// moves that got somewhere this attempt, bumps don't count
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    /// length of the shortest path, the score to beat
+    pub fn ideal_moves(&self) -> usize {
+        self.maze.ideal_path.len()
+    }
+
+    pub fn wall_bumps(&self) -> usize {
+        self.attempt.actions.len() - self.moves
+    }
+
+    pub fn hints(&self) -> usize {
+        self.attempt.hints
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.attempt.ending == Ending::Solved
+    }
+
+    /// Moves the player, false if a wall is in the way. Does nothing once the
+    /// maze is solved.
+    pub fn step(&mut self, direction: Direction) -> bool {
+        if self.is_solved() {
+            return false;
+        }
+        self.attempt.actions.push(direction);
+        self.attempt.times_ms.push(self.started.elapsed().as_millis() as u64);
+        let Some(position) = self.maze.neighbour(self.position, direction) else {
+            return false;
+        };
+        self.position = position;
+        self.moves += 1;
+        self.visited[position.1 as usize * self.maze.width + position.0 as usize] = true;
+        if position == self.maze.end_pos {
+            self.attempt.ending = Ending::Solved;
+        }
+        true
+    }
+
+    /// The first step of a shortest path from where the player stands, None
+    /// once the maze is solved. Every hint is recorded in the attempt.
+    pub fn hint(&mut self) -> Option<Direction> {
+        if self.is_solved() {
+            return None;
+        }
+        let mut from_here = self.maze.clone();
+        from_here.start_pos = self.position;
+        let direction = from_here.bfs_solve().ok()?.first().copied();
+        self.attempt.hints += 1;
+        direction
+    }
+
+    /// Puts the player back on start_pos of the same maze, keeping the
+    /// attempt so far as a trajectory if it had any moves
+    pub fn restart(&mut self) {
+        let fresh = Trajectory {
+            actions: vec![],
+            times_ms: vec![],
+            ending: Ending::Abandoned,
+            hints: 0,
+            ..self.attempt.clone()
+        };
+        let attempt = std::mem::replace(&mut self.attempt, fresh);
+        if !attempt.actions.is_empty() || attempt.hints > 0 {
+            self.attempts.push(attempt);
+        }
+        self.position = self.maze.start_pos;
+        self.moves = 0;
+        self.visited.fill(false);
+        self.visited[self.position.1 as usize * self.maze.width + self.position.0 as usize] = true;
+        self.started = Instant::now();
+    }
+
+    /// one trajectory per attempt so far, the current one included if it had
+    /// any moves. Attempts that didn't reach end_pos are Abandoned.
+    pub fn trajectories(&self) -> Vec<Trajectory> {
+        let mut attempts = self.attempts.clone();
+        if !self.attempt.actions.is_empty() || self.attempt.hints > 0 {
+            attempts.push(self.attempt.clone());
+        }
+        attempts
+    }
+
+    /// Renders the maze with the player and the cells it went through this
+    /// attempt. The shortest path is shown once the maze is solved.
+    pub fn render(&self, options: &RenderOptions) -> Result<String> {
+        self.maze.render(&RenderOptions {
+            solution: self.is_solved(),
+            visited: Some(self.visited.clone()),
+            agent: Some(self.position),
+            ..options.clone()
+        })
+    }
+}
+
+// raw mode on an alternate screen, restored on drop so errors don't leave
+// the terminal broken
+pub(crate) struct RawTerminal;
+impl RawTerminal {
+    pub(crate) fn enter() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(RawTerminal)
+    }
+}
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn key_direction(code: KeyCode) -> Option<Direction> {
+    match code {
+        KeyCode::Left | KeyCode::Char('a') => Some(Direction::Left),
+        KeyCode::Right | KeyCode::Char('d') => Some(Direction::Right),
+        KeyCode::Up | KeyCode::Char('w') => Some(Direction::Up),
+        KeyCode::Down | KeyCode::Char('s') => Some(Direction::Down),
+        _ => None,
+    }
+}
+
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Left => "left",
+        Direction::Right => "right",
+        Direction::Up => "up",
+        Direction::Down => "down",
+    }
+}
+
+fn draw(out: &mut impl Write, game: &Game, options: &RenderOptions, message: &str) -> Result<()> {
+    queue!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
+    // raw mode doesn't return the cursor to the start of the line
+    out.write_all(game.render(options)?.replace('\n', "\r\n").as_bytes())?;
+    let status = if game.is_solved() {
+        format!("solved in {} moves, shortest is {}. r to play again, q to quit", game.moves(), game.ideal_moves())
+    } else {
+        format!(
+            "seed {}  moves {}  shortest {}  bumps {}  hints {}",
+            game.seed(),
+            game.moves(),
+            game.ideal_moves(),
+            game.wall_bumps(),
+            game.hints()
+        )
+    };
+    write!(out, "{}\r\n{}\r\narrows/wasd move, h hint, r restart, q quit\r\n", status, message)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Plays `game` in the terminal until the player quits. Arrow keys or WASD
+/// move, h asks for a hint, r restarts the same maze and q or Esc quits.
+/// Game::trajectories has everything the player did afterwards.
+pub fn play(game: &mut Game, options: &RenderOptions) -> Result<()> {
+    let _terminal = RawTerminal::enter()?;
+    let mut stdout = io::stdout();
+    let mut message = String::new();
+    loop {
+        draw(&mut stdout, game, options, &message)?;
+        let Event::Key(key) = event::read()? else { continue };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        message.clear();
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Char('r') => game.restart(),
+            KeyCode::Char('h') => {
+                if let Some(direction) = game.hint() {
+                    message = format!("hint: go {}", direction_name(direction));
+                }
+            }
+            code => {
+                if let Some(direction) = key_direction(code) {
+                    if !game.step(direction) && !game.is_solved() {
+                        message = "there's a wall in the way".to_string();
+                    }
+                }
+            }
+        }
+    }
+}
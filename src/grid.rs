@@ -0,0 +1,181 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand::prelude::SliceRandom;
+use rand::Rng;
+
+/// The shape of a maze, separate from the algorithms that run on it: which
+/// cells there are, which cells neighbour each other and whether the wall
+/// between two neighbours stands. Maze is the rectangular grid, HexMaze,
+/// DeltaMaze and PolarMaze the other shapes. recursive_backtrack, bfs_path
+/// and the SVG renderer work on any of them.
+pub trait Grid {
+    /// where a cell is, e.g. (x, y) on a rectangular grid
+    type Pos: Copy + Eq + Hash + Debug;
+    /// a move from a cell across one of its sides
+    type Dir: Copy + Eq + Hash + Debug;
+
+    fn cell_count(&self) -> usize;
+
+    /// the index of `pos` into per-cell vectors, in 0..cell_count, None if
+    /// it isn't a cell
+    fn index(&self, pos: Self::Pos) -> Option<usize>;
+
+    /// the cell at `index`, the inverse of Grid::index
+    fn pos(&self, index: usize) -> Self::Pos;
+
+    /// every side of `pos`, those on the edge of the grid included, in an
+    /// order that stays the same so seeded generation stays the same
+    fn directions(&self, pos: Self::Pos) -> Vec<Self::Dir>;
+
+    /// the cell across the `direction` side of `pos`, None on the edge of the
+    /// grid. Walls are ignored
+    fn step(&self, pos: Self::Pos, direction: Self::Dir) -> Option<Self::Pos>;
+
+    /// true if there is a wall on the `direction` side of `pos`. Sides on the
+    /// edge of the grid always have one
+    fn has_wall(&self, pos: Self::Pos, direction: Self::Dir) -> bool;
+
+    /// Puts up or takes down the wall on the `direction` side of `pos`, and
+    /// so on the matching side of the neighbour. Does nothing on the edge
+    fn set_wall(&mut self, pos: Self::Pos, direction: Self::Dir, wall: bool);
+
+    /// the cell reached by moving from `pos` in `direction`, or None if a
+    /// wall is in the way
+    fn neighbour(&self, pos: Self::Pos, direction: Self::Dir) -> Option<Self::Pos> {
+        if self.has_wall(pos, direction) {
+            return None;
+        }
+        self.step(pos, direction)
+    }
+
+    /// the cells reachable from `pos` in one move, with the move
+    fn open_neighbours(&self, pos: Self::Pos) -> Vec<(Self::Dir, Self::Pos)> {
+        self.directions(pos)
+            .into_iter()
+            .filter_map(|direction| self.neighbour(pos, direction).map(|next| (direction, next)))
+            .collect()
+    }
+
+    /// Some(true) if a wall separates neighbours `a` and `b`, None if they
+    /// aren't neighbours
+    fn wall_between(&self, a: Self::Pos, b: Self::Pos) -> Option<bool> {
+        let direction = self.directions(a).into_iter().find(|&direction| self.step(a, direction) == Some(b))?;
+        Some(self.has_wall(a, direction))
+    }
+
+    fn open_wall(&mut self, pos: Self::Pos, direction: Self::Dir) {
+        self.set_wall(pos, direction, false);
+    }
+
+    fn close_wall(&mut self, pos: Self::Pos, direction: Self::Dir) {
+        self.set_wall(pos, direction, true);
+    }
+}
+
+/// Carves passages into `grid` with recursive backtracking from `start`,
+/// calling `on_carve(pos, direction)` for every wall it takes down, in order.
+/// Returns, per cell, whether carving reached it. Walls already down are
+/// ignored, the walk only looks at which cells it has been to.
+pub fn recursive_backtrack<G, R, F>(grid: &mut G, start: G::Pos, rng: &mut R, on_carve: &mut F) -> Vec<bool>
+where
+    G: Grid,
+    R: Rng,
+    F: FnMut(G::Pos, G::Dir),
+{
+    let mut visited = vec![false; grid.cell_count()];
+    visit(grid, start, &mut visited, rng, on_carve);
+    visited
+}
+
+fn visit<G, R, F>(grid: &mut G, pos: G::Pos, visited: &mut [bool], rng: &mut R, on_carve: &mut F)
+where
+    G: Grid,
+    R: Rng,
+    F: FnMut(G::Pos, G::Dir),
+{
+    let Some(i) = grid.index(pos) else { return };
+    visited[i] = true;
+    let mut directions = grid.directions(pos);
+    directions.shuffle(rng);
+    for direction in directions {
+        let Some(next) = grid.step(pos, direction) else { continue };
+        if grid.index(next).is_some_and(|i| !visited[i]) {
+            grid.open_wall(pos, direction);
+            on_carve(pos, direction);
+            visit(grid, next, visited, rng, on_carve);
+        }
+    }
+}
+
+/// A shortest path of moves from `start` to `end` through open walls, None
+/// if there is none or either isn't a cell
+pub fn bfs_path<G: Grid>(grid: &G, start: G::Pos, end: G::Pos) -> Option<Vec<G::Dir>> {
+    grid.index(end)?;
+    // the cell each one was reached from and the move that got there
+    let mut came_from: Vec<Option<(G::Pos, G::Dir)>> = vec![None; grid.cell_count()];
+    let mut seen = vec![false; grid.cell_count()];
+    seen[grid.index(start)?] = true;
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(pos) = queue.pop_front() {
+        if pos == end {
+            let mut path = vec![];
+            let mut at = pos;
+            while let Some((from, direction)) = grid.index(at).and_then(|i| came_from[i]) {
+                path.push(direction);
+                at = from;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for (direction, next) in grid.open_neighbours(pos) {
+            let Some(i) = grid.index(next) else { continue };
+            if !seen[i] {
+                seen[i] = true;
+                came_from[i] = Some((pos, direction));
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// Cells reached by a breadth first search from `start`, one layer per
+/// distance. The search stops at the layer that contains `end`.
+pub fn bfs_layers<G: Grid>(grid: &G, start: G::Pos, end: G::Pos) -> Vec<Vec<G::Pos>> {
+    let Some(first) = grid.index(start) else { return vec![] };
+    let mut seen = vec![false; grid.cell_count()];
+    seen[first] = true;
+    let mut layers = vec![vec![start]];
+
+    while !layers.last().is_some_and(|layer| layer.contains(&end)) {
+        let mut next = vec![];
+        for &pos in layers.last().into_iter().flatten() {
+            for (_, neighbour) in grid.open_neighbours(pos) {
+                if let Some(i) = grid.index(neighbour).filter(|&i| !seen[i]) {
+                    seen[i] = true;
+                    next.push(neighbour);
+                }
+            }
+        }
+        if next.is_empty() {
+            break;
+        }
+        layers.push(next);
+    }
+    layers
+}
+
+/// true if following `path` from `start` through open walls ends on `end`
+pub fn follows_path<G: Grid>(grid: &G, start: G::Pos, end: G::Pos, path: &[G::Dir]) -> bool {
+    let mut pos = start;
+    for &direction in path {
+        match grid.neighbour(pos, direction) {
+            Some(next) => pos = next,
+            None => return false,
+        }
+    }
+    pos == end
+}
@@ -0,0 +1,307 @@
+use std::path::Path;
+
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{MazeError, Result};
+use crate::grid::{self, Grid};
+use crate::maze_logic::check_dimensions;
+use crate::svg::{grid_svg, Edge, SvgLayout, SvgOptions};
+
+/// A move between neighbouring pointy topped hexagons. r grows downwards, so
+/// the north moves lower r.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum HexDirection {
+    East,
+    NorthEast,
+    NorthWest,
+    West,
+    SouthWest,
+    SouthEast,
+}
+impl HexDirection {
+    pub const ALL: [HexDirection; 6] = [
+        HexDirection::East,
+        HexDirection::NorthEast,
+        HexDirection::NorthWest,
+        HexDirection::West,
+        HexDirection::SouthWest,
+        HexDirection::SouthEast,
+    ];
+
+    /// (dq, dr) in axial coordinates
+    pub fn to_offset(self) -> (i16, i16) {
+        match self {
+            HexDirection::East => (1, 0),
+            HexDirection::NorthEast => (1, -1),
+            HexDirection::NorthWest => (0, -1),
+            HexDirection::West => (-1, 0),
+            HexDirection::SouthWest => (-1, 1),
+            HexDirection::SouthEast => (0, 1),
+        }
+    }
+
+    pub fn opposite(self) -> HexDirection {
+        match self {
+            HexDirection::East => HexDirection::West,
+            HexDirection::NorthEast => HexDirection::SouthWest,
+            HexDirection::NorthWest => HexDirection::SouthEast,
+            HexDirection::West => HexDirection::East,
+            HexDirection::SouthWest => HexDirection::NorthEast,
+            HexDirection::SouthEast => HexDirection::NorthWest,
+        }
+    }
+}
+
+/// A maze of pointy topped hexagons laid out in width x height rows, every
+/// odd row shifted half a cell to the right so the board stays rectangular.
+/// Positions are axial (q, r) with r the row; the per-cell vectors are row
+/// major over (column, row), see HexMaze::index. Each cell stores the three
+/// walls towards East, SouthEast and SouthWest, the other three belong to
+/// its neighbours. Walls on the edge of the board are always set.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HexMaze {
+    pub width: usize,
+    pub height: usize,
+    pub e_walls: Vec<bool>,
+    pub se_walls: Vec<bool>,
+    pub sw_walls: Vec<bool>,
+    /// shortest path from start_pos to end_pos, filled by bfs_solve
+    pub ideal_path: Vec<HexDirection>,
+    /// (q, r)
+    pub start_pos: (i16, i16),
+    /// (q, r)
+    pub end_pos: (i16, i16),
+}
+impl HexMaze {
+    /// Generates a hex maze with recursive backtracking and random, distinct
+    /// start and end positions. Same limits on the sides as Maze::new.
+    pub fn new(width: usize, height: usize) -> Result<Self> {
+        HexMaze::generate_with(width, height, &mut thread_rng())
+    }
+
+    /// same as new but draws everything from `rng`
+    pub fn generate_with<R: Rng>(width: usize, height: usize, rng: &mut R) -> Result<Self> {
+        check_dimensions(width, height)?;
+        let size = width * height;
+        let mut maze = HexMaze {
+            width,
+            height,
+            e_walls: vec![true; size],
+            se_walls: vec![true; size],
+            sw_walls: vec![true; size],
+            ideal_path: vec![],
+            start_pos: (0, 0),
+            end_pos: (0, 0),
+        };
+        loop {
+            let start = rng.gen_range(0..size);
+            let end = rng.gen_range(0..size);
+            if start != end {
+                maze.start_pos = maze.pos(start);
+                maze.end_pos = maze.pos(end);
+                break;
+            }
+        }
+        let first = maze.pos(rng.gen_range(0..size));
+        grid::recursive_backtrack(&mut maze, first, rng, &mut |_, _| {});
+        Ok(maze)
+    }
+
+    /// OutOfRange unless `pos` is a cell of this maze
+    pub fn check_pos(&self, pos: (i16, i16)) -> Result<()> {
+        match self.index(pos) {
+            Some(_) => Ok(()),
+            None => Err(MazeError::OutOfRange {
+                pos: (pos.0.max(0) as usize, pos.1.max(0) as usize),
+                width: self.width,
+                height: self.height,
+            }),
+        }
+    }
+
+    /// Finds a shortest path from start_pos to end_pos, storing it in
+    /// ideal_path. Returns Unsolvable, leaving ideal_path alone, if there is none.
+    pub fn bfs_solve(&mut self) -> Result<Vec<HexDirection>> {
+        self.check_pos(self.start_pos)?;
+        self.check_pos(self.end_pos)?;
+        let path = grid::bfs_path(self, self.start_pos, self.end_pos).ok_or(MazeError::Unsolvable)?;
+        self.ideal_path = path.clone();
+        Ok(path)
+    }
+
+    /// true if ideal_path leads from start_pos to end_pos without crossing a wall
+    pub fn can_follow_path(&self) -> bool {
+        grid::follows_path(self, self.start_pos, self.end_pos, &self.ideal_path)
+    }
+
+    /// Draws the maze with `/`, `\` and `|` walls, four characters per cell
+    /// and odd rows indented by two. `S` and `E` mark the start and end and
+    /// `*` the cells of ideal_path.
+    ///
+    /// ```text
+    ///  / \ / \
+    /// | S   E |
+    ///  \   \ / \
+    ///   |       |
+    ///    \ / \ /
+    /// ```
+    pub fn to_ascii(&self) -> String {
+        let columns = 4 * self.width + 3;
+        let mut canvas = vec![vec![' '; columns]; 2 * self.height + 1];
+        let mut on_path = vec![false; self.width * self.height];
+        let mut pos = self.start_pos;
+        for &direction in &self.ideal_path {
+            let (dq, dr) = direction.to_offset();
+            pos = (pos.0 + dq, pos.1 + dr);
+            if let Some(i) = self.index(pos) {
+                on_path[i] = true;
+            }
+        }
+
+        for (i, &on_path) in on_path.iter().enumerate() {
+            let pos = self.pos(i);
+            let row = i / self.width;
+            let (x, y) = (4 * (i % self.width) + 2 * (row % 2), 2 * row + 1);
+            let sides = [
+                (HexDirection::West, x, y, '|'),
+                (HexDirection::East, x + 4, y, '|'),
+                (HexDirection::NorthWest, x + 1, y - 1, '/'),
+                (HexDirection::NorthEast, x + 3, y - 1, '\\'),
+                (HexDirection::SouthWest, x + 1, y + 1, '\\'),
+                (HexDirection::SouthEast, x + 3, y + 1, '/'),
+            ];
+            for (direction, x, y, glyph) in sides {
+                if self.has_wall(pos, direction) {
+                    canvas[y][x] = glyph;
+                }
+            }
+            canvas[y][x + 2] = if pos == self.start_pos {
+                'S'
+            } else if pos == self.end_pos {
+                'E'
+            } else if on_path {
+                '*'
+            } else {
+                ' '
+            };
+        }
+
+        let mut out = String::new();
+        for line in canvas {
+            out.push_str(line.into_iter().collect::<String>().trim_end());
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Renders the maze with grid_svg, cell_size is the width of a hexagon
+    pub fn to_svg(&self, options: &SvgOptions) -> Result<String> {
+        grid_svg(self, self.start_pos, self.end_pos, &self.ideal_path, options)
+    }
+
+    pub fn write_svg(&self, path: &Path, options: &SvgOptions) -> Result<()> {
+        std::fs::write(path, self.to_svg(options)?)?;
+        Ok(())
+    }
+}
+
+impl Grid for HexMaze {
+    /// axial (q, r)
+    type Pos = (i16, i16);
+    type Dir = HexDirection;
+
+    fn cell_count(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn index(&self, (q, r): (i16, i16)) -> Option<usize> {
+        if r < 0 || r as usize >= self.height {
+            return None;
+        }
+        // odd rows are shifted right, so q runs back by one every two rows
+        let column = q + r / 2;
+        if column < 0 || column as usize >= self.width {
+            return None;
+        }
+        Some(r as usize * self.width + column as usize)
+    }
+
+    fn pos(&self, index: usize) -> (i16, i16) {
+        let (column, r) = ((index % self.width) as i16, (index / self.width) as i16);
+        (column - r / 2, r)
+    }
+
+    fn directions(&self, _: (i16, i16)) -> Vec<HexDirection> {
+        HexDirection::ALL.to_vec()
+    }
+
+    fn step(&self, pos: (i16, i16), direction: HexDirection) -> Option<(i16, i16)> {
+        self.index(pos)?;
+        let (dq, dr) = direction.to_offset();
+        let next = (pos.0 + dq, pos.1 + dr);
+        self.index(next).map(|_| next)
+    }
+
+    fn has_wall(&self, pos: (i16, i16), direction: HexDirection) -> bool {
+        let (Some(i), Some(next)) = (self.index(pos), self.step(pos, direction)) else {
+            return true;
+        };
+        match direction {
+            HexDirection::East => self.e_walls[i],
+            HexDirection::SouthEast => self.se_walls[i],
+            HexDirection::SouthWest => self.sw_walls[i],
+            _ => self.has_wall(next, direction.opposite()),
+        }
+    }
+
+    fn set_wall(&mut self, pos: (i16, i16), direction: HexDirection, wall: bool) {
+        let (Some(i), Some(next)) = (self.index(pos), self.step(pos, direction)) else {
+            return;
+        };
+        match direction {
+            HexDirection::East => self.e_walls[i] = wall,
+            HexDirection::SouthEast => self.se_walls[i] = wall,
+            HexDirection::SouthWest => self.sw_walls[i] = wall,
+            _ => self.set_wall(next, direction.opposite(), wall),
+        }
+    }
+}
+
+impl SvgLayout for HexMaze {
+    fn svg_size(&self, cell_size: f32) -> (f32, f32) {
+        let radius = cell_size / 3f32.sqrt();
+        (cell_size * (self.width as f32 + 0.5), radius * (0.5 + 1.5 * self.height as f32))
+    }
+
+    fn svg_centre(&self, pos: (i16, i16), cell_size: f32) -> (f32, f32) {
+        // circumradius, the distance from the centre to a corner
+        let radius = cell_size / 3f32.sqrt();
+        let i = self.index(pos).unwrap_or(0);
+        let (column, row) = (i % self.width, i / self.width);
+        let shift = if row % 2 == 1 { 0.5 } else { 0.0 };
+        (cell_size * (column as f32 + shift + 0.5), radius * (1.0 + 1.5 * row as f32))
+    }
+
+    fn svg_sides(&self, pos: (i16, i16), cell_size: f32) -> Vec<(HexDirection, Edge)> {
+        let radius = cell_size / 3f32.sqrt();
+        let (x, y) = self.svg_centre(pos, cell_size);
+        // corner k, clockwise from the one at the top
+        let corner = |k: usize| {
+            let angle = (k as f32 * 60.0 - 90.0).to_radians();
+            (x + radius * angle.cos(), y + radius * angle.sin())
+        };
+        [
+            HexDirection::NorthEast,
+            HexDirection::East,
+            HexDirection::SouthEast,
+            HexDirection::SouthWest,
+            HexDirection::West,
+            HexDirection::NorthWest,
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(k, direction)| (direction, Edge::Line { from: corner(k), to: corner((k + 1) % 6) }))
+        .collect()
+    }
+}
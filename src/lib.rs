@@ -0,0 +1,74 @@
+//! Maze generation and solving on square, hexagonal, triangular and polar
+//! grids, plus the tooling used to turn mazes into training data:
+//! deduplication, symmetry augmentation, split and sharded dataset writers, a
+//! streaming reader for the results, and a reinforcement learning
+//! environment over the same wall rules.
+//!
+//! ```no_run
+//! use maze::{DatasetReader, Maze};
+//!
+//! # fn main() -> maze::Result<()> {
+//! let mut maze = Maze::new(9, 9)?;
+//! maze.bfs_solve()?;
+//! maze.print()?;
+//!
+//! for record in DatasetReader::open("maze_train.json")? {
+//!     let maze = record?;
+//!     assert!(maze.can_follow_path());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Fallible functions return [`Result`], with a [`MazeError`] saying what went wrong.
+
+pub mod error;
+pub mod maze_logic;
+pub mod grid;
+pub mod symmetry;
+pub mod dataset;
+pub mod manifest;
+pub mod reader;
+pub mod compression;
+pub mod env;
+pub mod vec_env;
+pub mod rollout;
+pub mod decode;
+pub mod report;
+pub mod svg;
+pub mod render;
+pub mod parse;
+pub mod animate;
+pub mod game;
+pub mod trajectory;
+pub mod hex;
+pub mod delta;
+pub mod polar;
+
+pub use error::{MazeError, Result};
+pub use maze_logic::{Direction, Generator, Maze};
+pub use grid::{bfs_layers, bfs_path, follows_path, recursive_backtrack, Grid};
+pub use symmetry::Symmetry;
+pub use dataset::{
+    make_dataset, make_sharded, make_splits, mt_make_dataset, resume_sharded, Dedup, DedupFilter,
+    GenConfig, ResumeSummary, SizeRange, Split, SplitConfig,
+};
+pub use manifest::{Manifest, ShardInfo};
+pub use reader::{DatasetReader, ParallelDatasetReader, ReadError, ReadErrorKind};
+pub use compression::{CompressedWriter, Compression};
+pub use env::{EnvConfig, MazeEnv, Observation, ObservationKind, Rewards, StepInfo, Visibility};
+pub use vec_env::VecMazeEnv;
+pub use rollout::{
+    evaluate_policy, evaluate_policy_on, record_policy, record_policy_on, rollout_policy, rollout_policy_on,
+    ActionSelection, EpisodeStats, OnnxPolicy, RolloutReport,
+};
+pub use decode::{decode_path, edit_distance, repair_mask, repair_moves, DecodedPath, MaskRepair, MoveRepair};
+pub use report::{EvalReport, GroupStats, Outcome};
+pub use svg::{grid_svg, Edge, SvgLayout, SvgOptions};
+pub use render::{Charset, RenderOptions};
+pub use animate::{generation_gif, solving_gif, GifOptions};
+pub use game::{play, Game};
+pub use hex::{HexDirection, HexMaze};
+pub use delta::DeltaMaze;
+pub use polar::{PolarDirection, PolarMaze};
+pub use trajectory::{read_trajectories, replay, write_trajectories, Agent, Ending, MazeSource, Trajectory};
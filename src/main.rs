@@ -1,124 +1,156 @@
-use rand::prelude::*;
-use std::io::{self, Write, BufWriter};
-use std::sync::{Arc};
+use std::path::Path;
 use std::time::Instant;
-use serde_json;
-use std::fs::File;
-use std::thread;
 
+use anyhow::{bail, Context};
+use maze::*;
+
+// print speed benchmarks, see print::print_speed_test
+#[allow(dead_code)]
 mod print;
+#[allow(dead_code)]
 mod constants;
-mod maze_logic;
-
-use constants::*;
-use maze_logic::*;
 
-const MAX_MAZE_SIZE: usize = 10;
+const USAGE: &str = "usage:
+  maze                     write maze_{train,val,test}.json
+  maze eval <model.onnx> [--episodes N] [--seed S] [--local K] [--sample T] [--dataset PATH] [--json PATH]
+            [--trajectories PATH]
+                           roll out an ONNX policy, full 9x9 observations unless --local
+  maze play [--width W] [--height H] [--seed S] [--record PATH]
+                           play a maze in the terminal, --record writes every attempt as a trajectory
+  maze replay <trajectories.json> [--index N] [--bfs]
+                           step through a recorded trajectory, or the BFS path on its maze";
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        None => splits(),
+        Some("eval") => eval(&args[1..]),
+        Some("play") => play_game(&args[1..]),
+        Some("replay") => replay_trajectory(&args[1..]),
+        Some(_) => bail!("{}", USAGE),
+    }
+}
 
-fn main() {
-    /*
-    if let Err(e) = model_tester::main() {
-        eprintln!("Error: {:?}", e);
+fn splits() -> anyhow::Result<()> {
+    let config = GenConfig {
+        dedup: Dedup::Task,
+        symmetric: true,
+        ..GenConfig::default()
+    };
+    let now = Instant::now();
+    let counts = make_splits("maze", 1000000, &config, &SplitConfig::default())?;
+    for split in Split::ALL {
+        println!("{}: {} mazes", split.name(), counts[split as usize]);
     }
-    */
-    let mut now = Instant::now();
-    mt_make_dataset("test.json", 1000000);
-    println!("mt took {} s", now.elapsed().as_secs_f64());
-    now = Instant::now();
-    make_dataset("tests.json", 1000000);
-    println!("st took {} s", now.elapsed().as_secs_f64());
-    //print_speed_test();
+    println!("splits took {} s", now.elapsed().as_secs_f64());
+    //print::print_speed_test();
     //let mut maze = Maze::new(9,9);
     //maze.bfs_solve();
     //println!("can fallow path: {:?}", maze.can_follow_path());
+    Ok(())
 }
 
-fn mt_make_dataset(file_name: &str, size: usize) {
-    let num_cpus = num_cpus::get();
-    let times = size / num_cpus;
-    let mut handles = vec![];
-
-    // Create a vector to store the mazes, each thread will append to it
-    let mut thread_times = vec![times; num_cpus];
-    thread_times[num_cpus - 1] += size - (times * num_cpus);
-
-    // Create a shared vector to collect the mazes from all threads
-    let mut mazes = vec![Vec::new(); num_cpus]; // Vec of Vecs to hold results for each thread
-
-    for i in 0..num_cpus {
-        let times = thread_times[i];
-
-        let handle = thread::spawn(move || {
-            //let mut rng = thread_rng();
-            let mut local_maze = Vec::with_capacity(times); // Local vector for this thread
-
-            // Generate mazes
-            for j in 0..times {
-                if j % 99 == 0 {
-                    //println!("Thread {} generated {} mazes", i, j);
-                }
-                //let mut maze = Maze::new(rng.gen_range(1..=MAX_MAZE_SIZE) as usize, rng.gen_range(1..=MAX_MAZE_SIZE) as usize);
-                let mut maze = Maze::new(9, 9);
-                maze.set_pos();
-                maze.bfs_solve();
-                local_maze.push(maze);
-            }
-            local_maze // Return local vector of mazes
-        });
-        handles.push(handle);
+fn eval(args: &[String]) -> anyhow::Result<()> {
+    let Some(model) = args.first() else { bail!("{}", USAGE) };
+    let mut episodes = 1000;
+    let mut seed = 0;
+    let mut config = EnvConfig::default();
+    let mut selection = ActionSelection::Argmax;
+    let mut dataset = None;
+    let mut json = None;
+    let mut trajectories = None;
+
+    let mut flags = args[1..].iter();
+    while let Some(flag) = flags.next() {
+        let value = flags.next().with_context(|| format!("{} needs a value", flag))?;
+        match flag.as_str() {
+            "--episodes" => episodes = value.parse()?,
+            "--seed" => seed = value.parse()?,
+            "--local" => config.observation = ObservationKind::Local { size: value.parse()? },
+            "--sample" => selection = ActionSelection::Sample { temperature: value.parse()? },
+            "--dataset" => dataset = Some(value.clone()),
+            "--json" => json = Some(value.clone()),
+            "--trajectories" => trajectories = Some(value.clone()),
+            _ => bail!("unknown flag {}\n{}", flag, USAGE),
+        }
     }
 
-    // Collect all the mazes from threads
-
-    for (i, handle) in handles.into_iter().enumerate() {
-        let local_mazes = handle.join().unwrap(); // Get the result from the thread
-        mazes[i] = local_mazes; // Store it in the main mazes vector
+    let policy = OnnxPolicy::load(Path::new(model), config.observation_shape())?;
+    let recorded = match dataset {
+        Some(path) => {
+            let mazes = DatasetReader::open(&path)?.take(episodes).collect::<std::result::Result<Vec<_>, _>>()?;
+            record_policy_on(&policy, &config, mazes, seed, selection)?
+        }
+        None => record_policy(&policy, &config, episodes, seed, selection)?,
+    };
+    let (stats, recorded): (Vec<_>, Vec<_>) = recorded.into_iter().unzip();
+    if let Some(path) = trajectories {
+        write_trajectories(Path::new(&path), &recorded)?;
     }
 
-    // Flatten all thread results into a single vector
-    let all_mazes: Vec<Maze> = mazes.into_iter().flatten().collect();
-
-    // Write the mazes to a file
-    let file = File::create(file_name).expect("was not able to create file");
-    let mut writer = BufWriter::new(file);
-    for maze in all_mazes {
-        writeln!(writer, "{}", serde_json::to_string(&maze).unwrap()).expect("failed to write");
+    let report = EvalReport::from_episodes(&stats);
+    print!("{}", report);
+    let totals = RolloutReport::from_episodes(&stats);
+    println!(
+        "wall bumps per episode {:.2}, revisits per episode {:.2}, {} of {} episodes looped",
+        totals.mean_wall_bumps, totals.mean_revisits, totals.looped_episodes, totals.episodes
+    );
+    if let Some(path) = json {
+        report.write_json(Path::new(&path))?;
     }
+    Ok(())
 }
 
-fn make_dataset(file_name: &str, size: usize){
-
+fn play_game(args: &[String]) -> anyhow::Result<()> {
+    let (mut width, mut height) = (15, 10);
+    let mut seed = rand::random();
+    let mut record = None;
+
+    let mut flags = args.iter();
+    while let Some(flag) = flags.next() {
+        let value = flags.next().with_context(|| format!("{} needs a value", flag))?;
+        match flag.as_str() {
+            "--width" => width = value.parse()?,
+            "--height" => height = value.parse()?,
+            "--seed" => seed = value.parse()?,
+            "--record" => record = Some(value.clone()),
+            _ => bail!("unknown flag {}\n{}", flag, USAGE),
+        }
+    }
 
-    let mut rng = thread_rng();
-    let file = File::create(file_name).expect("failed to create file");
-    let mut writer = BufWriter::new(file);
+    let mut game = Game::new(width, height, seed)?;
+    play(&mut game, &RenderOptions::default())?;
+    let attempts = game.trajectories();
+    if let Some(path) = record {
+        write_trajectories(Path::new(&path), &attempts)?;
+    }
+    let solved = attempts.iter().filter(|attempt| attempt.ending == Ending::Solved).count();
+    println!("seed {}: solved {} of {} attempts", seed, solved, attempts.len());
+    Ok(())
+}
 
-    for i in 0..size {
-        //let mut maze = Maze::new(rng.gen_range(1..=MAX_MAZE_SIZE) as usize, rng.gen_range(1..=255) as usize);
-        let mut maze = Maze::new(9, 9);
-        //maze.gen_maze();
-        //maze.set_pos();
-        //maze.solve();
-        maze.bfs_solve();
-        /*
-        maze.print();
-        if (!maze.ideal_path.is_empty()){
-        if (maze.can_follow_path()){
-        println!("Solved");
-        }
-        }
-        else {
-        println!("Can Not Solve");
+fn replay_trajectory(args: &[String]) -> anyhow::Result<()> {
+    let Some(path) = args.first() else { bail!("{}", USAGE) };
+    let mut index = 0;
+    let mut bfs = false;
+
+    let mut flags = args[1..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "--bfs" => bfs = true,
+            "--index" => index = flags.next().with_context(|| format!("{} needs a value", flag))?.parse()?,
+            _ => bail!("unknown flag {}\n{}", flag, USAGE),
         }
-        maze.print_pos();
-        */
+    }
 
-        let line = serde_json::to_string(&maze).unwrap();
-        writeln!(writer, "{}", line).expect("was not able to write to file");
-        /*
-        if i % 1000 == 0 {
-            println!("{}", i);
-        }
-        */
+    let trajectories = read_trajectories(Path::new(path))?;
+    let Some(mut trajectory) = trajectories.into_iter().nth(index) else {
+        bail!("{} has no trajectory {}", path, index)
+    };
+    if bfs {
+        trajectory = Trajectory::bfs(trajectory.maze)?;
     }
+    let maze = trajectory.maze.resolve()?;
+    replay(&trajectory, &maze, &RenderOptions { solution: false, ..RenderOptions::default() })?;
+    Ok(())
 }
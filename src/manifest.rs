@@ -0,0 +1,133 @@
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::dataset::GenConfig;
+use crate::error::Result;
+
+/// Bumped whenever the layout of a Maze record in the shards changes
+pub const SCHEMA_VERSION: u32 = 2;
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ShardInfo {
+    /// file name relative to the manifest
+    pub file: String,
+    /// dataset index of the first record in the shard
+    pub first_index: usize,
+    pub count: usize,
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+/// Describes a sharded dataset so loaders can split shards between workers
+/// without opening them first.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Manifest {
+    pub schema_version: u32,
+    pub crate_version: String,
+    pub seed: u64,
+    /// records requested, total_count can fall short if dedup gave up
+    pub target_count: usize,
+    /// records in finished shards
+    pub total_count: usize,
+    pub shard_size: usize,
+    pub config: GenConfig,
+    /// finished shards only, a shard missing from here is still being written
+    pub shards: Vec<ShardInfo>,
+    pub complete: bool,
+}
+impl Manifest {
+    pub fn new(seed: u64, target_count: usize, shard_size: usize, config: &GenConfig) -> Self {
+        Manifest {
+            schema_version: SCHEMA_VERSION,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            seed,
+            target_count,
+            total_count: 0,
+            shard_size,
+            config: config.clone(),
+            shards: vec![],
+            complete: false,
+        }
+    }
+
+    pub fn num_shards(&self) -> usize {
+        self.target_count.div_ceil(self.shard_size)
+    }
+
+    /// records a finished shard, keeping shards in index order
+    pub fn add_shard(&mut self, shard: ShardInfo) {
+        self.total_count += shard.count;
+        let at = self.shards.partition_point(|other| other.first_index < shard.first_index);
+        self.shards.insert(at, shard);
+    }
+
+    pub fn read(path: &Path) -> Result<Manifest> {
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+
+    /// writes to a temporary file first so a crash never leaves a torn manifest
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("json.tmp");
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writeln!(writer)?;
+        writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }
+}
+
+pub fn shard_file_name(shard: usize) -> String {
+    format!("shard-{:05}.json", shard)
+}
+
+/// (bytes, hex sha256) of the file at `path`, as HashingWriter::finish
+/// reports them for a shard written in one go
+pub fn hash_file(path: &Path) -> io::Result<(u64, String)> {
+    let mut writer = HashingWriter::new(io::sink());
+    io::copy(&mut BufReader::new(File::open(path)?), &mut writer)?;
+    writer.finish()
+}
+
+/// Writer that keeps a running sha256 and byte count of everything written
+/// through it, so shards don't have to be read back to checksum them.
+pub struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+    bytes: u64,
+}
+impl<W: Write> HashingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        HashingWriter { inner, hasher: Sha256::new(), bytes: 0 }
+    }
+
+    /// for appending to a file that already holds `prefix`
+    pub fn with_prefix(inner: W, prefix: &[u8]) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(prefix);
+        HashingWriter { inner, hasher, bytes: prefix.len() as u64 }
+    }
+
+    /// flushes the inner writer and returns (bytes, hex sha256)
+    pub fn finish(mut self) -> io::Result<(u64, String)> {
+        self.inner.flush()?;
+        Ok((self.bytes, format!("{:x}", self.hasher.finalize())))
+    }
+}
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -1,12 +1,15 @@
-use std::{io::{self, Write}, mem};
-use std::collections::{HashSet, VecDeque};
+use std::io::{self, Write};
 use rand::*;
-use rand::rngs::ThreadRng;
-use crate::constants::*;
 use serde::{Serialize, Deserialize};
-use rand::prelude::SliceRandom;
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+use crate::error::{MazeError, Result};
+use crate::grid::{self, Grid};
+
+// positions are stored as u8
+const MAX_SIDE: usize = u8::MAX as usize + 1;
+
+/// A move between neighbouring cells, y grows downwards
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Direction {
     Left,
     Right,
@@ -14,7 +17,10 @@ pub enum Direction {
     Down,
 }
 impl Direction {
-    fn to_offset(&self) -> (i16, i16) {
+    pub const ALL: [Direction; 4] = [Direction::Left, Direction::Right, Direction::Up, Direction::Down];
+
+    /// (dx, dy)
+    pub fn to_offset(self) -> (i16, i16) {
         match self {
             Direction::Left => (-1, 0),
             Direction::Right => (1, 0),
@@ -22,23 +28,70 @@ impl Direction {
             Direction::Down => (0, 1),
         }
     }
+
+    /// the move making a (dx, dy) step of ideal_path, None if it isn't one
+    pub fn from_offset(dx: i8, dy: i8) -> Option<Direction> {
+        Direction::ALL.into_iter().find(|direction| direction.to_offset() == (dx as i16, dy as i16))
+    }
 }
 
+/// Algorithm used to carve the passages of a maze
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Generator {
+    #[default]
+    RecursiveBacktrack,
+}
+
+/// A rectangular maze. Every per-cell vector is row major, index y * width + x.
+/// This is also the record format of every dataset file.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Maze {
     pub width: usize,
     pub height: usize,
+    /// cells visited by the generator
     pub cells: Vec<bool>,
+    /// wall on the right side of each cell, always set on the last column
     pub r_walls: Vec<bool>,
+    /// wall below each cell, always set on the last row
     pub b_walls: Vec<bool>,
+    /// shortest path from start_pos to end_pos as (dx, dy) steps, filled by bfs_solve
     pub ideal_path: Vec<(i8,i8)>,
+    /// (x, y)
     pub start_pos: (u8, u8),
+    /// (x, y)
     pub end_pos: (u8, u8),
+    /// records written before this field existed were all recursive backtracking
+    #[serde(default)]
+    pub generator: Generator,
 }
 impl Maze {
-    pub fn new(width: usize, height: usize) -> Self {
+    /// Generates a maze with recursive backtracking and random start and end
+    /// positions. Needs at least two cells and at most 256 on each side.
+    pub fn new(width: usize, height: usize) -> Result<Self> {
+        Maze::generate(width, height, Generator::RecursiveBacktrack)
+    }
+
+    /// Generates a maze with `generator` and random start and end positions
+    pub fn generate(width: usize, height: usize, generator: Generator) -> Result<Self> {
+        Maze::generate_with(width, height, generator, &mut thread_rng())
+    }
+
+    /// same as generate but draws everything from `rng`, so a seeded rng
+    /// always gives the same maze
+    pub fn generate_with<R: Rng>(width: usize, height: usize, generator: Generator, rng: &mut R) -> Result<Self> {
+        let mut maze = Maze::blank(width, height, generator)?;
+        maze.set_pos_with(rng)?;
+        match generator {
+            Generator::RecursiveBacktrack => maze.gen_maze_with(rng),
+        }
+        Ok(maze)
+    }
+
+    // every wall standing and no cell visited yet
+    pub(crate) fn blank(width: usize, height: usize, generator: Generator) -> Result<Self> {
+        check_dimensions(width, height)?;
         let size: usize = width * height;
-        let mut maze = Maze {
+        Ok(Maze {
             width,
             height,
             cells: vec![false; size],
@@ -47,14 +100,19 @@ impl Maze {
             ideal_path: vec![],
             start_pos: (0, 0),
             end_pos: (0, 0),
-        };
-        maze.set_pos();
-        maze.gen_maze();
-        return maze;
+            generator,
+        })
     }
 
-    pub fn set_pos(&mut self) {
-        let mut rng = rand::thread_rng();
+    /// Picks new, distinct start and end positions
+    pub fn set_pos(&mut self) -> Result<()> {
+        self.set_pos_with(&mut thread_rng())
+    }
+
+    /// same as set_pos but draws the positions from `rng`
+    pub fn set_pos_with<R: Rng>(&mut self, rng: &mut R) -> Result<()> {
+        // there is no distinct end for a single cell
+        check_dimensions(self.width, self.height)?;
         loop {
             self.start_pos = (
                 rng.gen_range(0..self.width) as u8,
@@ -65,102 +123,106 @@ impl Maze {
                 rng.gen_range(0..self.height) as u8
             );
             if self.start_pos != self.end_pos {
-                break;
+                return Ok(());
             }
         }
     }
 
+    /// Sets start_pos and end_pos, both (x, y)
+    pub fn set_positions(&mut self, start_pos: (u8, u8), end_pos: (u8, u8)) -> Result<()> {
+        self.check_pos(start_pos)?;
+        self.check_pos(end_pos)?;
+        self.start_pos = start_pos;
+        self.end_pos = end_pos;
+        Ok(())
+    }
+
+    /// OutOfRange unless `pos` is a cell of this maze
+    pub fn check_pos(&self, pos: (u8, u8)) -> Result<()> {
+        let pos = (pos.0 as usize, pos.1 as usize);
+        if pos.0 < self.width && pos.1 < self.height {
+            Ok(())
+        } else {
+            Err(MazeError::OutOfRange { pos, width: self.width, height: self.height })
+        }
+    }
+
     pub fn print_pos(&self){
         println!("start_pos:{:?} end_pos:{:?}", self.start_pos, self.end_pos);
     }
 
-    pub fn bfs_solve(&mut self) -> Option<Vec<Direction>> {
-        let mut queue = VecDeque::new();
-        let mut visited = HashSet::new();
-        let mut came_from = std::collections::HashMap::new();
-
-        queue.push_back((self.start_pos, Vec::new()));
-        visited.insert(self.start_pos);
-
-        while let Some((current_pos, path)) = queue.pop_front() {
-            if current_pos == self.end_pos {
-                self.ideal_path = path
-                    .iter()
-                    .map(|direction| match direction {
-                        Direction::Left => (-1, 0),
-                        Direction::Right => (1, 0),
-                        Direction::Up => (0, -1),
-                        Direction::Down => (0, 1),
-                    })
-                    .collect();
-                return Some(path);
-            }
-
-            for &direction in &[Direction::Left, Direction::Right, Direction::Up, Direction::Down] {
+    /// Finds a shortest path from start_pos to end_pos, storing it in
+    /// ideal_path. Returns Unsolvable, leaving ideal_path alone, if there is none.
+    pub fn bfs_solve(&mut self) -> Result<Vec<Direction>> {
+        self.check_pos(self.start_pos)?;
+        self.check_pos(self.end_pos)?;
+        let path = grid::bfs_path(self, self.start_pos, self.end_pos).ok_or(MazeError::Unsolvable)?;
+        self.ideal_path = path
+            .iter()
+            .map(|direction| {
                 let (dx, dy) = direction.to_offset();
-                let new_pos = (
-                    (current_pos.0 as i16 + dx) as u8,
-                    (current_pos.1 as i16 + dy) as u8,
-                );
-
-                if !self.is_edge(&current_pos, &(dx, dy)) 
-                && self.can_move(current_pos, direction) 
-                && !visited.contains(&new_pos) {
-                    let mut new_path = path.clone();
-                    new_path.push(direction);
-                    queue.push_back((new_pos, new_path));
-                    visited.insert(new_pos);
-                    came_from.insert(new_pos, (current_pos, direction));
-                }
-            }
-        }
-
-        None
+                (dx as i8, dy as i8)
+            })
+            .collect();
+        Ok(path)
     }
 
+    /// true if moving by `direction` from `cur_pos` leaves the maze
     pub fn is_edge(&self, cur_pos: &(u8, u8), direction: &(i16, i16)) -> bool {
         let new_pos = (cur_pos.0 as i16 + direction.0, cur_pos.1 as i16 + direction.1);
         new_pos.0 < 0 || new_pos.1 < 0 || new_pos.0 >= self.width as i16 || new_pos.1 >= self.height as i16
     }
 
-    fn can_move(&self, pos: (u8, u8), direction: Direction) -> bool {
+    /// true if there is no wall between `pos` and its neighbour in `direction`,
+    /// and both are inside the maze
+    pub fn can_move(&self, pos: (u8, u8), direction: Direction) -> bool {
+        if self.index(pos).is_none() {
+            return false;
+        }
         let (x, y) = (pos.0 as usize, pos.1 as usize);
         match direction {
             Direction::Left => x > 0 && !self.r_walls[y * self.width + x - 1],
             Direction::Right => x < self.width - 1 && !self.r_walls[y * self.width + x],
-            Direction::Up => y > 0 && self.b_walls[(y - 1) * self.width + x],
+            Direction::Up => y > 0 && !self.b_walls[(y - 1) * self.width + x],
             Direction::Down => y < self.height - 1 && !self.b_walls[y * self.width + x],
         }
     }
 
-    fn can_move_path(&self, pos: (u8, u8), direction: (i8, i8)) -> bool {
-        let (x, y) = (pos.0 as usize, pos.1 as usize);
-        match direction {
-            (-1, 0) => x > 0 && !self.r_walls[y * self.width + x - 1],
-            (1, 0) => x < self.width - 1 && !self.r_walls[y * self.width + x],
-            (0, -1) => y > 0 && !self.b_walls[(y - 1) * self.width + x],
-            (0, 1) => y < self.height - 1 && !self.b_walls[y * self.width + x],
-            _ => false,
+    /// the cell reached by moving from `pos` in `direction`, or None if a wall
+    /// or the edge of the maze is in the way or `pos` is outside it
+    pub fn neighbour(&self, pos: (u8, u8), direction: Direction) -> Option<(u8, u8)> {
+        if !self.can_move(pos, direction) {
+            return None;
         }
+        let (dx, dy) = direction.to_offset();
+        Some(((pos.0 as i16 + dx) as u8, (pos.1 as i16 + dy) as u8))
     }
 
+    /// ideal_path as moves, up to the first step that isn't one
+    pub fn ideal_directions(&self) -> Vec<Direction> {
+        self.ideal_path.iter().map_while(|&(dx, dy)| Direction::from_offset(dx, dy)).collect()
+    }
+
+    /// true if ideal_path leads from start_pos to end_pos without crossing a wall
     pub fn can_follow_path(&self) -> bool {
-        let mut cur_pos = self.start_pos;
-        for &direction in &self.ideal_path {
-            if self.can_move_path(cur_pos, direction) {
-                cur_pos.0 = cur_pos.0.wrapping_add(direction.0 as u8);
-                cur_pos.1 = cur_pos.1.wrapping_add(direction.1 as u8);
-            } else {
-                return false;
-            }
-        }
-        cur_pos == self.end_pos
+        let path = self.ideal_directions();
+        path.len() == self.ideal_path.len() && grid::follows_path(self, self.start_pos, self.end_pos, &path)
     }
 
-    pub fn print(&self) {
+    /// Prints the maze to stdout with `|` and `-` walls, `S` and `E` for the
+    /// start and end and `*` for visited cells. print_with can also show the
+    /// solution and overlays
+    pub fn print(&self) -> Result<()> {
         let stdout = io::stdout();
         let mut handle = stdout.lock();
-        let mut buffer: Vec<u8> = Vec::with_capacity((self.width * 4 * self.height + self.height) as usize);
+        handle.write_all(self.to_ascii().as_bytes())?;
+        handle.flush()?;
+        Ok(())
+    }
+
+    /// What print writes, Maze::from_ascii reads it back
+    pub fn to_ascii(&self) -> String {
+        let mut buffer: Vec<u8> = Vec::with_capacity(self.width * 4 * self.height + self.height);
         let wall_option = [b' ', b'|'];
         let cell_option = [b' ', b'*'];
         let floor_option = [b' ', b'-'];
@@ -195,16 +257,18 @@ impl Maze {
             buffer.push(b'\n');
         }
 
-        handle.write_all(&buffer).unwrap();
-        handle.flush().unwrap();
+        // every byte pushed above is ASCII
+        String::from_utf8(buffer).unwrap_or_default()
     }
     //doesnt correctly display start and end
-    pub fn z_print(&self) {
+    pub fn z_print(&self) -> Result<()> {
+        self.check_pos(self.start_pos)?;
+        self.check_pos(self.end_pos)?;
         let stdout = io::stdout();
         let mut handle = stdout.lock();
         //WIDTH * 3 accounts for cell and r_wall and b_wall
         // + HEIGHT is for \n
-        let mut buffer: Vec<u8> = Vec::with_capacity((self.width * 4 * self.height + self.height) as usize);
+        let mut buffer: Vec<u8> = Vec::with_capacity(self.width * 4 * self.height + self.height);
         let wall_option = [b'|', b' '];
         let cell_option = [b'*', b' '];
         let floor_option = [b'-', b' '];
@@ -245,132 +309,143 @@ impl Maze {
         buffer[start_index] = b'S';
         buffer[end_index] = b'E';
 
-        handle.write_all(&buffer).unwrap();
-        handle.flush().unwrap();
+        handle.write_all(&buffer)?;
+        handle.flush()?;
+        Ok(())
     }
 
+    /// Carves passages with recursive backtracking from a random cell
     pub fn gen_maze(&mut self) {
-        let mut visited: HashSet<(usize, usize)> = HashSet::new();
-        let mut rng = thread_rng();
-        let start_pos: [usize;2]  = [rng.gen_range(0..self.width), rng.gen_range(0..self.height)];
-        self.recursive_backtrack(start_pos[0], start_pos[1], &mut visited, &mut rng);
+        self.gen_maze_with(&mut thread_rng());
     }
 
-    fn recursive_backtrack(&mut self, x: usize, y: usize, visited: &mut HashSet<(usize, usize)>, rng: &mut ThreadRng) {
-        visited.insert((x, y));
-        self.cells[y * self.width + x] = true;  // Mark as path
+    /// same as gen_maze but draws everything from `rng`
+    pub fn gen_maze_with<R: Rng>(&mut self, rng: &mut R) {
+        self.gen_maze_traced(rng, &mut |_, _, _| {});
+    }
 
-        let directions = [(0, -1), (1, 0), (0, 1), (-1, 0)];
-        let mut shuffled_directions = directions.to_vec();
-        shuffled_directions.shuffle(rng);
+    /// gen_maze_with, calling `on_carve(x, y, direction)` for every wall it
+    /// removes, in order. The first call is from the cell carving starts in
+    pub(crate) fn gen_maze_traced<R: Rng, F: FnMut(usize, usize, Direction)>(&mut self, rng: &mut R, on_carve: &mut F) {
+        let start_pos = (rng.gen_range(0..self.width) as u8, rng.gen_range(0..self.height) as u8);
+        let visited = grid::recursive_backtrack(self, start_pos, rng, &mut |pos: (u8, u8), direction| {
+            on_carve(pos.0 as usize, pos.1 as usize, direction)
+        });
+        for (cell, visited) in self.cells.iter_mut().zip(visited) {
+            *cell |= visited;
+        }
+    }
 
-        for (dx, dy) in shuffled_directions {
-            let next_x = x as i32 + dx;
-            let next_y = y as i32 + dy;
+    pub(crate) fn remove_wall(&mut self, x: usize, y: usize, direction: Direction) {
+        self.open_wall((x as u8, y as u8), direction);
+    }
+}
 
-            //checks if the position it is trying to go it is 
-            //past top, bottom, left, and right walls
+impl Grid for Maze {
+    type Pos = (u8, u8);
+    type Dir = Direction;
 
-            if next_x >= 0 && next_x < self.width as i32 && next_y >= 0 && next_y < self.height as i32 
-            && !visited.contains(&(next_x as usize, next_y as usize)) {
-                // Remove wall between current cell and next cell
-                let wall_x = x as i32;
-                let wall_y = y as i32;
-                self.remove_wall(wall_x as usize, wall_y as usize, (dx, dy));
+    fn cell_count(&self) -> usize {
+        self.width * self.height
+    }
 
-                self.recursive_backtrack(next_x as usize, next_y as usize, visited, rng);
-            }
+    fn index(&self, pos: (u8, u8)) -> Option<usize> {
+        self.check_pos(pos).ok()?;
+        Some(pos.1 as usize * self.width + pos.0 as usize)
+    }
+
+    fn pos(&self, index: usize) -> (u8, u8) {
+        ((index % self.width) as u8, (index / self.width) as u8)
+    }
+
+    // the order recursive backtracking has always shuffled, so seeded mazes
+    // come out the same
+    fn directions(&self, _: (u8, u8)) -> Vec<Direction> {
+        vec![Direction::Up, Direction::Right, Direction::Down, Direction::Left]
+    }
+
+    fn step(&self, pos: (u8, u8), direction: Direction) -> Option<(u8, u8)> {
+        self.index(pos)?;
+        let (dx, dy) = direction.to_offset();
+        if self.is_edge(&pos, &(dx, dy)) {
+            return None;
         }
+        Some(((pos.0 as i16 + dx) as u8, (pos.1 as i16 + dy) as u8))
     }
 
-    fn remove_wall(&mut self, x: usize, y: usize, direction: (i32,i32)) {
+    fn has_wall(&self, pos: (u8, u8), direction: Direction) -> bool {
+        self.step(pos, direction).is_none() || !self.can_move(pos, direction)
+    }
+
+    // bfs_solve has always tried Left, Right, Up, Down, keeping that order
+    // keeps the same ideal_path when there are several shortest ones
+    fn open_neighbours(&self, pos: (u8, u8)) -> Vec<(Direction, (u8, u8))> {
+        [Direction::Left, Direction::Right, Direction::Up, Direction::Down]
+            .into_iter()
+            .filter_map(|direction| Grid::neighbour(self, pos, direction).map(|next| (direction, next)))
+            .collect()
+    }
+
+    fn set_wall(&mut self, pos: (u8, u8), direction: Direction, wall: bool) {
+        if self.step(pos, direction).is_none() {
+            return;
+        }
+        let (x, y) = (pos.0 as usize, pos.1 as usize);
         match direction {
-            //UP
-            (0, -1) => self.b_walls[(y - 1) * self.width + x] = false,
-            //RIGHT
-            (1, 0) => self.r_walls[y * self.width + x] = false,
-            //DOWN
-            (0, 1) => self.b_walls[y * self.width + x] = false,
-            //LEFT
-            (-1, 0) => self.r_walls[y * self.width + x - 1] = false,
-            _ => panic!("invalid direction (fn remove_wall)"),
+            Direction::Up => self.b_walls[(y - 1) * self.width + x] = wall,
+            Direction::Right => self.r_walls[y * self.width + x] = wall,
+            Direction::Down => self.b_walls[y * self.width + x] = wall,
+            Direction::Left => self.r_walls[y * self.width + x - 1] = wall,
         }
     }
 }
 
-pub struct Maze_r {
-    cells: [bool; WIDTH * HEIGHT],
-    l_walls: [bool; WIDTH * HEIGHT],
-    r_walls: [bool; WIDTH * HEIGHT],
-    t_walls: [bool; WIDTH * HEIGHT],
-    b_walls: [bool; WIDTH * HEIGHT]
+pub(crate) fn check_dimensions(width: usize, height: usize) -> Result<()> {
+    if width == 0 || height == 0 || width * height < 2 || width > MAX_SIDE || height > MAX_SIDE {
+        return Err(MazeError::InvalidDimensions { width, height });
+    }
+    Ok(())
 }
-impl Maze_r {
-    pub fn new() -> Self {
-        return Maze_r { 
-            cells: [false; (WIDTH * HEIGHT) as usize],
-            l_walls: [false; (WIDTH * HEIGHT) as usize],
-            r_walls: [false; (WIDTH * HEIGHT) as usize],
-            t_walls: [false; (WIDTH * HEIGHT) as usize],
-            b_walls: [false; (WIDTH * HEIGHT) as usize]}
-    }
-    pub fn get_size(&self) -> usize {
-        mem::size_of::<Self>()
-    }
-    pub fn print(&self) {
-        let stdout = io::stdout();
-        let mut handle = stdout.lock();
-        //WIDTH * 3 accounts for cell and r_wall and b_wall
-        // + HEIGHT is for \n
-        let mut buffer: Vec<u8> = Vec::with_capacity(WIDTH * HEIGHT + HEIGHT);
-        //let cell_option = [b'*', b' '];
 
-        for row in 0..HEIGHT{
-            let cell_slice = &self.cells[(HEIGHT*row)..(HEIGHT*row + WIDTH)];
-            buffer.extend(cell_slice
-                .iter()
-                .map(|&cell| if !cell { b'*'} else { b' '}));
-            buffer.push(b'\n');
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
 
-        handle.write_all(&buffer).unwrap();
-        handle.flush().unwrap();
+    fn maze() -> Maze {
+        Maze::generate_with(5, 4, Generator::RecursiveBacktrack, &mut StdRng::seed_from_u64(5)).unwrap()
     }
-}
 
+    #[test]
+    fn moves_from_outside_the_maze_are_refused() {
+        let maze = maze();
+        for direction in Direction::ALL {
+            for pos in [(5, 0), (0, 4), (255, 255)] {
+                assert!(!maze.can_move(pos, direction));
+                assert_eq!(maze.neighbour(pos, direction), None);
+            }
+        }
+    }
 
-pub struct Maze_ro {
-    cells: [bool; WIDTH * HEIGHT],
-    l_walls: [bool; WIDTH * HEIGHT],
-    r_walls: [bool; WIDTH * HEIGHT],
-    t_walls: [bool; WIDTH * HEIGHT],
-    b_walls: [bool; WIDTH * HEIGHT]
-}
-impl Maze_ro {
-    pub fn new() -> Self {
-        return Maze_ro { 
-            cells: [false; (WIDTH * HEIGHT) as usize],
-            l_walls: [false; (WIDTH * HEIGHT) as usize],
-            r_walls: [false; (WIDTH * HEIGHT) as usize],
-            t_walls: [false; (WIDTH * HEIGHT) as usize],
-            b_walls: [false; (WIDTH * HEIGHT) as usize]}
-    }
-    pub fn get_size(&self) -> usize {
-        mem::size_of::<Self>()
-    }
-    pub fn print(&self) {
-        let stdout = io::stdout();
-        let mut handle = stdout.lock();
-        //WIDTH * 3 accounts for cell and r_wall and b_wall
-        // + HEIGHT is for \n
-        let mut buffer: Vec<u8> = Vec::with_capacity(WIDTH * HEIGHT + HEIGHT);
-        //let cell_option = [b'*', b' '];
-        for chunk in self.cells.chunks(WIDTH){
-            buffer.extend(chunk.iter().map(|cell| if !cell {b'*'} else {b' '}));
-            buffer.push(b'\n');
+    #[test]
+    fn moves_stay_inside_the_maze() {
+        let maze = maze();
+        for y in 0..4 {
+            assert!(!maze.can_move((0, y), Direction::Left));
+            assert!(!maze.can_move((4, y), Direction::Right));
         }
+        for x in 0..5 {
+            assert!(!maze.can_move((x, 0), Direction::Up));
+            assert!(!maze.can_move((x, 3), Direction::Down));
+        }
+    }
 
-        handle.write_all(&buffer).unwrap();
-        handle.flush().unwrap();
+    #[test]
+    fn seeded_mazes_repeat() {
+        let mut a = maze();
+        let mut b = maze();
+        assert_eq!((a.r_walls.clone(), a.b_walls.clone()), (b.r_walls.clone(), b.b_walls.clone()));
+        assert_eq!(a.bfs_solve().unwrap(), b.bfs_solve().unwrap());
+        assert!(a.can_follow_path());
     }
 }
@@ -0,0 +1,180 @@
+use std::str::FromStr;
+
+use crate::error::{MazeError, Result};
+use crate::maze_logic::{Generator, Maze};
+
+fn error(line: usize, column: usize, message: impl Into<String>) -> MazeError {
+    MazeError::Parse { line: line + 1, column: column + 1, message: message.into() }
+}
+
+// the character at `column`, trailing whitespace that editors strip counts as spaces
+fn char_at(line: &[char], column: usize) -> char {
+    line.get(column).copied().unwrap_or(' ')
+}
+
+// checks that `line` has `expected` at `column`, naming what belongs there
+fn expect(lines: &[Vec<char>], line: usize, column: usize, expected: &[char], what: &str) -> Result<char> {
+    let found = char_at(&lines[line], column);
+    if expected.contains(&found) {
+        Ok(found)
+    } else {
+        Err(error(line, column, format!("expected {}, found {:?}", what, found)))
+    }
+}
+
+impl Maze {
+    /// Parses the layout Maze::print and to_ascii write: a ceiling of `-`, then for every
+    /// row a line of cells with `|` walls and a line of `-` floors. Cells are
+    /// `S`, `E`, `*` or a space, and exactly one `S` and one `E` are needed.
+    /// Trailing whitespace and blank lines at the end don't matter.
+    ///
+    /// ```text
+    ///  - - -
+    /// |S *|*|
+    ///      -
+    /// |* *|E|
+    ///  - - -
+    /// ```
+    ///
+    /// Every cell is marked visited except blank ones, and ideal_path is left
+    /// empty for bfs_solve to fill.
+    pub fn from_ascii(text: &str) -> Result<Maze> {
+        let mut lines: Vec<Vec<char>> = text.lines().map(|line| line.trim_end().chars().collect()).collect();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        if lines.len() < 3 {
+            return Err(error(lines.len(), 0, "expected a ceiling and at least one row of cells"));
+        }
+        if lines.len().is_multiple_of(2) {
+            return Err(error(lines.len() - 1, 0, "expected a floor line after the last row of cells"));
+        }
+
+        // the first row of cells is the widest line, ending in the right border
+        let first_row = &lines[1];
+        if first_row.len() < 3 || first_row.len().is_multiple_of(2) {
+            return Err(error(1, first_row.len(), "row doesn't end in a right wall after a whole number of cells"));
+        }
+        let width = (first_row.len() - 1) / 2;
+        let height = (lines.len() - 1) / 2;
+        // positions are stored as u8
+        if width > u8::MAX as usize + 1 || height > u8::MAX as usize + 1 {
+            return Err(MazeError::InvalidDimensions { width, height });
+        }
+        for (i, line) in lines.iter().enumerate() {
+            if line.len() > 2 * width + 1 {
+                return Err(error(i, 2 * width + 1, format!("line is longer than a {} cell wide maze", width)));
+            }
+        }
+
+        let mut maze = Maze {
+            width,
+            height,
+            cells: vec![false; width * height],
+            r_walls: vec![false; width * height],
+            b_walls: vec![false; width * height],
+            ideal_path: vec![],
+            start_pos: (0, 0),
+            end_pos: (0, 0),
+            generator: Generator::default(),
+        };
+        let mut start = None;
+        let mut end = None;
+
+        for x in 0..width {
+            expect(&lines, 0, 2 * x, &[' '], "a space")?;
+            expect(&lines, 0, 2 * x + 1, &['-'], "a ceiling '-'")?;
+        }
+        for y in 0..height {
+            let row = 2 * y + 1;
+            let floor = 2 * y + 2;
+            expect(&lines, row, 0, &['|'], "a left wall '|'")?;
+            expect(&lines, floor, 0, &[' '], "a space")?;
+
+            for x in 0..width {
+                let index = y * width + x;
+                let cell = expect(&lines, row, 2 * x + 1, &[' ', '*', 'S', 'E'], "a cell ' ', '*', 'S' or 'E'")?;
+                let marker = match cell {
+                    'S' => Some(&mut start),
+                    'E' => Some(&mut end),
+                    _ => None,
+                };
+                if let Some(marker) = marker {
+                    if marker.is_some() {
+                        return Err(error(row, 2 * x + 1, format!("second {:?}", cell)));
+                    }
+                    *marker = Some((x as u8, y as u8));
+                }
+                maze.cells[index] = cell != ' ';
+
+                let last_column = x == width - 1;
+                let (walls, what): (&[char], _) = if last_column {
+                    (&['|'], "a right wall '|'")
+                } else {
+                    (&['|', ' '], "a wall '|' or a space")
+                };
+                maze.r_walls[index] = expect(&lines, row, 2 * x + 2, walls, what)? == '|';
+
+                let (floors, what): (&[char], _) = if y == height - 1 {
+                    (&['-'], "a bottom wall '-'")
+                } else {
+                    (&['-', ' '], "a floor '-' or a space")
+                };
+                maze.b_walls[index] = expect(&lines, floor, 2 * x + 1, floors, what)? == '-';
+                expect(&lines, floor, 2 * x + 2, &[' '], "a space")?;
+            }
+        }
+
+        let last = lines.len() - 1;
+        maze.start_pos = start.ok_or_else(|| error(last, 0, "no start 'S'"))?;
+        maze.end_pos = end.ok_or_else(|| error(last, 0, "no end 'E'"))?;
+        Ok(maze)
+    }
+}
+
+impl FromStr for Maze {
+    type Err = MazeError;
+
+    fn from_str(text: &str) -> Result<Maze> {
+        Maze::from_ascii(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn round_trip(width: usize, height: usize, seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let maze = Maze::generate_with(width, height, Generator::RecursiveBacktrack, &mut rng).unwrap();
+        let parsed = Maze::from_ascii(&maze.to_ascii()).unwrap();
+        assert_eq!((parsed.width, parsed.height), (maze.width, maze.height));
+        assert_eq!(parsed.r_walls, maze.r_walls);
+        assert_eq!(parsed.b_walls, maze.b_walls);
+        assert_eq!((parsed.start_pos, parsed.end_pos), (maze.start_pos, maze.end_pos));
+        assert_eq!(parsed.to_ascii(), maze.to_ascii());
+    }
+
+    #[test]
+    fn to_ascii_round_trips() {
+        round_trip(9, 9, 0);
+        round_trip(12, 5, 1);
+    }
+
+    #[test]
+    fn one_wide_mazes_round_trip() {
+        round_trip(1, 6, 2);
+        round_trip(6, 1, 3);
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let Err(MazeError::Parse { line, column, .. }) = Maze::from_ascii(" - -\n|S#E|\n - -\n") else {
+            panic!("expected a parse error");
+        };
+        assert_eq!((line, column), (2, 3));
+        assert!(Maze::from_ascii(" - -\n|S  |\n - -\n").is_err());
+    }
+}
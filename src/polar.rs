@@ -0,0 +1,282 @@
+use std::f32::consts::TAU;
+use std::path::Path;
+
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{MazeError, Result};
+use crate::grid::{self, Grid};
+use crate::svg::{grid_svg, Edge, SvgLayout, SvgOptions};
+
+// keeps the outer ring to a few hundred cells
+const MAX_RINGS: usize = 64;
+
+/// A move between neighbouring cells of a PolarMaze. Clockwise is towards
+/// higher indices around a ring.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PolarDirection {
+    Clockwise,
+    CounterClockwise,
+    /// towards the centre
+    Inward,
+    /// to the k-th of the cells just outside this one, counting clockwise
+    Outward(usize),
+}
+
+/// A circular maze of concentric rings around a single centre cell. Each
+/// ring has as many cells as keeps them roughly square, a whole multiple of
+/// the ring inside it, so a cell touches one cell further in and one or more
+/// further out. Positions are (ring, index) with ring 0 the centre, and the
+/// per-cell vectors run ring by ring, see PolarMaze::index.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PolarMaze {
+    /// cells in each ring, the centre first
+    pub ring_sizes: Vec<usize>,
+    /// wall on the clockwise side of each cell, unused for the centre
+    pub cw_walls: Vec<bool>,
+    /// wall between each cell and the ring inside it, unused for the centre
+    pub inward_walls: Vec<bool>,
+    /// shortest path from start_pos to end_pos, filled by bfs_solve
+    pub ideal_path: Vec<PolarDirection>,
+    /// (ring, index)
+    pub start_pos: (usize, usize),
+    /// (ring, index)
+    pub end_pos: (usize, usize),
+}
+impl PolarMaze {
+    /// Generates a polar maze of `rings` rings, the centre included, with
+    /// recursive backtracking and random, distinct start and end positions.
+    /// Needs 2 to 64 rings.
+    pub fn new(rings: usize) -> Result<Self> {
+        PolarMaze::generate_with(rings, &mut thread_rng())
+    }
+
+    /// same as new but draws everything from `rng`
+    pub fn generate_with<R: Rng>(rings: usize, rng: &mut R) -> Result<Self> {
+        if !(2..=MAX_RINGS).contains(&rings) {
+            return Err(MazeError::InvalidConfig(format!("a polar maze needs 2 to {} rings, got {}", MAX_RINGS, rings)));
+        }
+        let ring_sizes = PolarMaze::ring_sizes_for(rings);
+        let size: usize = ring_sizes.iter().sum();
+        let mut maze = PolarMaze {
+            ring_sizes,
+            cw_walls: vec![true; size],
+            inward_walls: vec![true; size],
+            ideal_path: vec![],
+            start_pos: (0, 0),
+            end_pos: (0, 0),
+        };
+        loop {
+            let start = rng.gen_range(0..size);
+            let end = rng.gen_range(0..size);
+            if start != end {
+                maze.start_pos = maze.pos(start);
+                maze.end_pos = maze.pos(end);
+                break;
+            }
+        }
+        let first = maze.pos(rng.gen_range(0..size));
+        grid::recursive_backtrack(&mut maze, first, rng, &mut |_, _| {});
+        Ok(maze)
+    }
+
+    /// Cells per ring for `rings` rings of equal width. Each ring splits
+    /// every cell of the ring inside it into as many cells as keeps their
+    /// outer edge about as long as the ring is wide, 6 around the centre.
+    pub fn ring_sizes_for(rings: usize) -> Vec<usize> {
+        let mut sizes = vec![1];
+        for ring in 1..rings {
+            let previous = sizes[ring - 1];
+            // in ring widths, the circumference at the inner edge of the ring
+            let circumference = TAU * ring as f32;
+            let ratio = (circumference / previous as f32).round().max(1.0) as usize;
+            sizes.push(previous * ratio);
+        }
+        sizes
+    }
+
+    pub fn rings(&self) -> usize {
+        self.ring_sizes.len()
+    }
+
+    /// OutOfRange unless `pos` is a cell of this maze, reported with the ring
+    /// count as the height and the outer ring's size as the width
+    pub fn check_pos(&self, pos: (usize, usize)) -> Result<()> {
+        match self.index(pos) {
+            Some(_) => Ok(()),
+            None => Err(MazeError::OutOfRange {
+                pos,
+                width: self.ring_sizes.last().copied().unwrap_or(0),
+                height: self.rings(),
+            }),
+        }
+    }
+
+    // cells in the next ring out for each cell of `ring`, 0 on the outer ring
+    fn split(&self, ring: usize) -> usize {
+        match self.ring_sizes.get(ring + 1) {
+            Some(&outer) => outer / self.ring_sizes[ring],
+            None => 0,
+        }
+    }
+
+    /// Finds a shortest path from start_pos to end_pos, storing it in
+    /// ideal_path. Returns Unsolvable, leaving ideal_path alone, if there is none.
+    pub fn bfs_solve(&mut self) -> Result<Vec<PolarDirection>> {
+        self.check_pos(self.start_pos)?;
+        self.check_pos(self.end_pos)?;
+        let path = grid::bfs_path(self, self.start_pos, self.end_pos).ok_or(MazeError::Unsolvable)?;
+        self.ideal_path = path.clone();
+        Ok(path)
+    }
+
+    /// true if ideal_path leads from start_pos to end_pos without crossing a wall
+    pub fn can_follow_path(&self) -> bool {
+        grid::follows_path(self, self.start_pos, self.end_pos, &self.ideal_path)
+    }
+
+    /// Renders the maze with grid_svg, cell_size is the width of a ring.
+    /// Curved walls are `<path class="wall">` arcs.
+    pub fn to_svg(&self, options: &SvgOptions) -> Result<String> {
+        grid_svg(self, self.start_pos, self.end_pos, &self.ideal_path, options)
+    }
+
+    pub fn write_svg(&self, path: &Path, options: &SvgOptions) -> Result<()> {
+        std::fs::write(path, self.to_svg(options)?)?;
+        Ok(())
+    }
+}
+
+impl Grid for PolarMaze {
+    /// (ring, index)
+    type Pos = (usize, usize);
+    type Dir = PolarDirection;
+
+    fn cell_count(&self) -> usize {
+        self.ring_sizes.iter().sum()
+    }
+
+    fn index(&self, (ring, i): (usize, usize)) -> Option<usize> {
+        if ring >= self.rings() || i >= self.ring_sizes[ring] {
+            return None;
+        }
+        Some(self.ring_sizes[..ring].iter().sum::<usize>() + i)
+    }
+
+    fn pos(&self, mut index: usize) -> (usize, usize) {
+        for (ring, &size) in self.ring_sizes.iter().enumerate() {
+            if index < size {
+                return (ring, index);
+            }
+            index -= size;
+        }
+        (self.rings(), index)
+    }
+
+    /// the sides of a cell in the outer ring include one Outward side, the
+    /// edge of the maze
+    fn directions(&self, (ring, _): (usize, usize)) -> Vec<PolarDirection> {
+        let mut directions = vec![];
+        if ring > 0 {
+            directions.extend([PolarDirection::Clockwise, PolarDirection::CounterClockwise, PolarDirection::Inward]);
+        }
+        directions.extend((0..self.split(ring).max(1)).map(PolarDirection::Outward));
+        directions
+    }
+
+    fn step(&self, (ring, i): (usize, usize), direction: PolarDirection) -> Option<(usize, usize)> {
+        self.index((ring, i))?;
+        let size = self.ring_sizes[ring];
+        match direction {
+            PolarDirection::Clockwise if ring > 0 => Some((ring, (i + 1) % size)),
+            PolarDirection::CounterClockwise if ring > 0 => Some((ring, (i + size - 1) % size)),
+            PolarDirection::Inward if ring > 0 => Some((ring - 1, i / self.split(ring - 1))),
+            PolarDirection::Outward(k) if k < self.split(ring) => Some((ring + 1, i * self.split(ring) + k)),
+            _ => None,
+        }
+    }
+
+    fn has_wall(&self, pos: (usize, usize), direction: PolarDirection) -> bool {
+        let (Some(i), Some(next)) = (self.index(pos), self.step(pos, direction)) else {
+            return true;
+        };
+        match direction {
+            PolarDirection::Clockwise => self.cw_walls[i],
+            PolarDirection::Inward => self.inward_walls[i],
+            PolarDirection::CounterClockwise => self.has_wall(next, PolarDirection::Clockwise),
+            PolarDirection::Outward(_) => self.has_wall(next, PolarDirection::Inward),
+        }
+    }
+
+    fn set_wall(&mut self, pos: (usize, usize), direction: PolarDirection, wall: bool) {
+        let (Some(i), Some(next)) = (self.index(pos), self.step(pos, direction)) else {
+            return;
+        };
+        match direction {
+            PolarDirection::Clockwise => self.cw_walls[i] = wall,
+            PolarDirection::Inward => self.inward_walls[i] = wall,
+            PolarDirection::CounterClockwise => self.set_wall(next, PolarDirection::Clockwise, wall),
+            PolarDirection::Outward(_) => self.set_wall(next, PolarDirection::Inward, wall),
+        }
+    }
+}
+
+impl PolarMaze {
+    // svg coordinates at `radius` from the middle, `turn` of the way round
+    // clockwise from east
+    fn svg_point(&self, radius: f32, turn: f32, cell_size: f32) -> (f32, f32) {
+        let middle = cell_size * self.rings() as f32;
+        let angle = turn * TAU;
+        (middle + radius * angle.cos(), middle + radius * angle.sin())
+    }
+
+    // the fraction of a turn where the cell starts and ends
+    fn turns(&self, (ring, i): (usize, usize)) -> (f32, f32) {
+        let size = self.ring_sizes[ring] as f32;
+        (i as f32 / size, (i + 1) as f32 / size)
+    }
+}
+
+impl SvgLayout for PolarMaze {
+    fn svg_size(&self, cell_size: f32) -> (f32, f32) {
+        let diameter = 2.0 * cell_size * self.rings() as f32;
+        (diameter, diameter)
+    }
+
+    fn svg_centre(&self, pos: (usize, usize), cell_size: f32) -> (f32, f32) {
+        if pos.0 == 0 {
+            return self.svg_point(0.0, 0.0, cell_size);
+        }
+        let (from, to) = self.turns(pos);
+        self.svg_point((pos.0 as f32 + 0.5) * cell_size, (from + to) / 2.0, cell_size)
+    }
+
+    /// round the outer edge, one arc per cell further out, then in along
+    /// the clockwise side, back along the inner edge and out again
+    fn svg_sides(&self, pos: (usize, usize), cell_size: f32) -> Vec<(PolarDirection, Edge)> {
+        let (from, to) = self.turns(pos);
+        let (inner, outer) = (pos.0 as f32 * cell_size, (pos.0 + 1) as f32 * cell_size);
+        let point = |radius: f32, turn: f32| self.svg_point(radius, turn, cell_size);
+        let arc = |radius: f32, start: f32, end: f32, clockwise: bool| Edge::Arc {
+            from: point(radius, start),
+            to: point(radius, end),
+            radius,
+            clockwise,
+        };
+
+        let outward = self.split(pos.0).max(1);
+        let span = (to - from) / outward as f32;
+        let mut sides: Vec<(PolarDirection, Edge)> = (0..outward)
+            .map(|k| {
+                let start = from + span * k as f32;
+                (PolarDirection::Outward(k), arc(outer, start, start + span, true))
+            })
+            .collect();
+        if pos.0 > 0 {
+            sides.push((PolarDirection::Clockwise, Edge::Line { from: point(outer, to), to: point(inner, to) }));
+            sides.push((PolarDirection::Inward, arc(inner, to, from, false)));
+            sides.push((PolarDirection::CounterClockwise, Edge::Line { from: point(inner, from), to: point(outer, from) }));
+        }
+        sides
+    }
+}
@@ -1,9 +1,9 @@
 use rand::prelude::*;
 use std::time::*;
 use std::io::{self, Write};
+use std::mem;
 
 use crate::constants::*;
-use crate::maze_logic;
 
 pub fn print_maze(arr: &[[(bool, [bool; 4]); WIDTH]; HEIGHT]) {
     let options = ['*', ' '];
@@ -125,10 +125,10 @@ pub fn detailed_maze_print(arr: &[[(bool, [bool; 4]); WIDTH]; HEIGHT]) {
         //buffer.extend(row.iter().map(|&(_, neighbor)| [TOP[neighbor[2]], TOP[neighbor[2]]]).flatten());
         //buffer.push(b'\n');
         //prints left space right
-        buffer.extend(row.iter().map(|&(cell, neighbors)| [OPTION[cell as usize],WALL[neighbors[1] as usize]]).flatten());
+        buffer.extend(row.iter().flat_map(|&(cell, neighbors)| [OPTION[cell as usize],WALL[neighbors[1] as usize]]));
         buffer.push(b'\n');
         //prints bottom
-        buffer.extend(row.iter().map(|&(_, neighbor)| [TOP[neighbor[3] as usize], TOP[neighbor[3] as usize]]).flatten());
+        buffer.extend(row.iter().flat_map(|&(_, neighbor)| [TOP[neighbor[3] as usize], TOP[neighbor[3] as usize]]));
         buffer.push(b'\n');
     }
 
@@ -154,7 +154,7 @@ pub fn print_real_maze_uo(arr: &[[(bool, [bool; 4]); WIDTH]; HEIGHT]) {
                 print!(" ");
             }
         }
-        println!("");
+        println!();
         for col in row{
             if !col.1[3] {
                 print!("__");
@@ -163,7 +163,7 @@ pub fn print_real_maze_uo(arr: &[[(bool, [bool; 4]); WIDTH]; HEIGHT]) {
                 print!("  ");
             }
         }
-        println!("");
+        println!();
     }
 }
 
@@ -179,13 +179,13 @@ pub fn maze_print_speed_test(maze: &[[(bool, [bool; 4]); WIDTH]; HEIGHT], times:
     for (order,func) in print_funcs.iter().enumerate(){
         for _ in 0..times{
             let now = Instant::now();
-            func(&maze);
+            func(maze);
             elasped_time[order] += now.elapsed().as_micros();
         }
     }
 
-    let maze = maze_logic::Maze_r::new();
-    let maze_o = maze_logic::Maze_ro::new();
+    let maze = Maze_r::new();
+    let maze_o = Maze_ro::new();
 
 
     for _ in 0..times{
@@ -216,7 +216,7 @@ pub fn maze_print_speed_test(maze: &[[(bool, [bool; 4]); WIDTH]; HEIGHT], times:
 
 pub fn print_speed_test(){
     let mut rng = thread_rng();
-    let mut maze_ = [[(false, [false; 4]); WIDTH as usize]; HEIGHT as usize];
+    let mut maze_ = [[(false, [false; 4]); WIDTH]; HEIGHT];
 
     for row in maze_.iter_mut() {
         for cell in row.iter_mut() {
@@ -226,3 +226,82 @@ pub fn print_speed_test(){
 
     maze_print_speed_test(&maze_, 10000);
 }
+
+#[allow(non_camel_case_types)]
+pub struct Maze_r {
+    cells: [bool; WIDTH * HEIGHT],
+    l_walls: [bool; WIDTH * HEIGHT],
+    r_walls: [bool; WIDTH * HEIGHT],
+    t_walls: [bool; WIDTH * HEIGHT],
+    b_walls: [bool; WIDTH * HEIGHT]
+}
+impl Maze_r {
+    pub fn new() -> Self {
+        Maze_r { 
+            cells: [false; WIDTH * HEIGHT],
+            l_walls: [false; WIDTH * HEIGHT],
+            r_walls: [false; WIDTH * HEIGHT],
+            t_walls: [false; WIDTH * HEIGHT],
+            b_walls: [false; WIDTH * HEIGHT]}
+    }
+    pub fn get_size(&self) -> usize {
+        mem::size_of::<Self>()
+    }
+    pub fn print(&self) {
+        let stdout = io::stdout();
+        let mut handle = stdout.lock();
+        //WIDTH * 3 accounts for cell and r_wall and b_wall
+        // + HEIGHT is for \n
+        let mut buffer: Vec<u8> = Vec::with_capacity(WIDTH * HEIGHT + HEIGHT);
+        //let cell_option = [b'*', b' '];
+
+        for row in 0..HEIGHT{
+            let cell_slice = &self.cells[(HEIGHT*row)..(HEIGHT*row + WIDTH)];
+            buffer.extend(cell_slice
+                .iter()
+                .map(|&cell| if !cell { b'*'} else { b' '}));
+            buffer.push(b'\n');
+        }
+
+        handle.write_all(&buffer).unwrap();
+        handle.flush().unwrap();
+    }
+}
+
+
+#[allow(non_camel_case_types)]
+pub struct Maze_ro {
+    cells: [bool; WIDTH * HEIGHT],
+    l_walls: [bool; WIDTH * HEIGHT],
+    r_walls: [bool; WIDTH * HEIGHT],
+    t_walls: [bool; WIDTH * HEIGHT],
+    b_walls: [bool; WIDTH * HEIGHT]
+}
+impl Maze_ro {
+    pub fn new() -> Self {
+        Maze_ro { 
+            cells: [false; WIDTH * HEIGHT],
+            l_walls: [false; WIDTH * HEIGHT],
+            r_walls: [false; WIDTH * HEIGHT],
+            t_walls: [false; WIDTH * HEIGHT],
+            b_walls: [false; WIDTH * HEIGHT]}
+    }
+    pub fn get_size(&self) -> usize {
+        mem::size_of::<Self>()
+    }
+    pub fn print(&self) {
+        let stdout = io::stdout();
+        let mut handle = stdout.lock();
+        //WIDTH * 3 accounts for cell and r_wall and b_wall
+        // + HEIGHT is for \n
+        let mut buffer: Vec<u8> = Vec::with_capacity(WIDTH * HEIGHT + HEIGHT);
+        //let cell_option = [b'*', b' '];
+        for chunk in self.cells.chunks(WIDTH){
+            buffer.extend(chunk.iter().map(|cell| if !cell {b'*'} else {b' '}));
+            buffer.push(b'\n');
+        }
+
+        handle.write_all(&buffer).unwrap();
+        handle.flush().unwrap();
+    }
+}
@@ -0,0 +1,254 @@
+use flate2::read::MultiGzDecoder;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+use crate::error::Result as MazeResult;
+use crate::manifest::*;
+use crate::maze_logic::Maze;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+// lines handed to each thread per batch when decoding in parallel
+const LINES_PER_THREAD: usize = 1024;
+
+#[derive(Debug)]
+pub enum ReadErrorKind {
+    Io(io::Error),
+    /// the line isn't valid UTF-8
+    Utf8(std::string::FromUtf8Error),
+    Parse(serde_json::Error),
+}
+
+/// A record that couldn't be read, with the file and 1-based line it came from
+#[derive(Debug)]
+pub struct ReadError {
+    pub path: PathBuf,
+    pub line: usize,
+    pub kind: ReadErrorKind,
+}
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            ReadErrorKind::Io(e) => write!(f, "{}:{}: {}", self.path.display(), self.line, e),
+            ReadErrorKind::Utf8(e) => write!(f, "{}:{}: invalid UTF-8: {}", self.path.display(), self.line, e),
+            ReadErrorKind::Parse(e) => write!(f, "{}:{}: malformed record: {}", self.path.display(), self.line, e),
+        }
+    }
+}
+impl std::error::Error for ReadError {}
+
+// an undecoded line with the file and line number it came from
+type RawLine = (Arc<Path>, usize, String);
+
+/// opens a file, transparently decompressing gzip or zstd based on its first bytes
+pub fn open_decompressed(path: &Path) -> io::Result<Box<dyn BufRead + Send>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let magic = reader.fill_buf()?;
+    Ok(if magic.starts_with(&GZIP_MAGIC) {
+        Box::new(BufReader::new(MultiGzDecoder::new(reader)))
+    } else if magic.starts_with(&ZSTD_MAGIC) {
+        Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?))
+    } else {
+        Box::new(reader)
+    })
+}
+
+/// Iterates the Maze records of a JSONL dataset, one per line. Accepts a
+/// single file, plain or compressed, or a directory written by make_sharded,
+/// in which case its shards are read in order. Malformed lines come out as
+/// errors and reading carries on with the next line; blank lines are ignored.
+pub struct DatasetReader {
+    // files still to read, in order
+    paths: VecDeque<PathBuf>,
+    current: Option<(Arc<Path>, Box<dyn BufRead + Send>)>,
+    line: usize,
+    skip: usize,
+    limit: Option<usize>,
+    yielded: usize,
+    buffer: Vec<u8>,
+}
+impl DatasetReader {
+    pub fn open(path: &str) -> MazeResult<Self> {
+        let path = Path::new(path);
+        let paths = if path.is_dir() {
+            let manifest = Manifest::read(&path.join(MANIFEST_FILE))?;
+            manifest.shards.iter().map(|shard| path.join(&shard.file)).collect()
+        } else {
+            VecDeque::from([path.to_path_buf()])
+        };
+
+        Ok(DatasetReader {
+            paths,
+            current: None,
+            line: 0,
+            skip: 0,
+            limit: None,
+            yielded: 0,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// skips the first `records` non-blank lines without parsing them
+    pub fn skip_records(mut self, records: usize) -> Self {
+        self.skip = records;
+        self
+    }
+
+    /// stops after `records` records, errors included
+    pub fn limit_records(mut self, records: usize) -> Self {
+        self.limit = Some(records);
+        self
+    }
+
+    /// decodes records on `threads` threads, still yielding them in file order
+    pub fn parallel(self, threads: usize) -> ParallelDatasetReader {
+        ParallelDatasetReader { reader: self, threads: threads.max(1), decoded: VecDeque::new(), failed: false }
+    }
+
+    fn lines(&mut self) -> Lines<'_> {
+        Lines(self)
+    }
+
+    fn error(&self, path: &Path, kind: ReadErrorKind) -> ReadError {
+        ReadError { path: path.to_path_buf(), line: self.line, kind }
+    }
+
+    // the next non-blank line along with where it came from
+    fn next_line(&mut self) -> Option<Result<RawLine, ReadError>> {
+        loop {
+            if self.limit.is_some_and(|limit| self.yielded >= limit) {
+                return None;
+            }
+
+            if self.current.is_none() {
+                let path = self.paths.pop_front()?;
+                self.line = 0;
+                match open_decompressed(&path) {
+                    Ok(reader) => self.current = Some((path.into(), reader)),
+                    Err(e) => return Some(Err(self.error(&path, ReadErrorKind::Io(e)))),
+                }
+            }
+
+            let (path, reader) = self.current.as_mut().unwrap();
+            self.buffer.clear();
+            // read as bytes so a line of invalid UTF-8 is one bad record, not the
+            // end of the file
+            let read = reader.read_until(b'\n', &mut self.buffer);
+            self.line += 1;
+            match read {
+                Ok(0) => {
+                    self.current = None;
+                }
+                Ok(_) if self.buffer.iter().all(u8::is_ascii_whitespace) => {}
+                Ok(_) if self.skip > 0 => {
+                    self.skip -= 1;
+                }
+                Ok(_) => {
+                    self.yielded += 1;
+                    let path = Arc::clone(path);
+                    return Some(match String::from_utf8(std::mem::take(&mut self.buffer)) {
+                        Ok(text) => Ok((path, self.line, text)),
+                        Err(e) => Err(self.error(&path, ReadErrorKind::Utf8(e))),
+                    });
+                }
+                Err(e) => {
+                    // a broken file can't be read past the error, move on to the next one
+                    let path = Arc::clone(path);
+                    self.current = None;
+                    self.yielded += 1;
+                    return Some(Err(self.error(&path, ReadErrorKind::Io(e))));
+                }
+            }
+        }
+    }
+}
+impl Iterator for DatasetReader {
+    type Item = Result<Maze, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.next_line()?.and_then(|(path, line, text)| decode(path, line, &text)))
+    }
+}
+
+fn decode(path: Arc<Path>, line: usize, text: &str) -> Result<Maze, ReadError> {
+    serde_json::from_str(text).map_err(|e| ReadError { path: path.to_path_buf(), line, kind: ReadErrorKind::Parse(e) })
+}
+
+/// DatasetReader that parses batches of lines on several threads
+pub struct ParallelDatasetReader {
+    reader: DatasetReader,
+    threads: usize,
+    decoded: VecDeque<Result<Maze, ReadError>>,
+    // a decoding thread panicked, nothing is read after its error
+    failed: bool,
+}
+impl ParallelDatasetReader {
+    fn fill(&mut self) {
+        let mut chunks = vec![];
+        for _ in 0..self.threads {
+            let chunk: Vec<_> = self.reader.lines().take(LINES_PER_THREAD).collect();
+            if chunk.is_empty() {
+                break;
+            }
+            chunks.push(chunk);
+        }
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = chunks
+                .into_iter()
+                .map(|chunk| {
+                    // where the batch starts, to say which one was lost if its thread panics
+                    let (path, line) = match &chunk[0] {
+                        Ok((path, line, _)) => (path.to_path_buf(), *line),
+                        Err(e) => (e.path.clone(), e.line),
+                    };
+                    let handle = scope.spawn(move || {
+                        chunk
+                            .into_iter()
+                            .map(|line| line.and_then(|(path, number, text)| decode(path, number, &text)))
+                            .collect::<Vec<_>>()
+                    });
+                    (path, line, handle)
+                })
+                .collect();
+            // every thread is joined, scope would panic over one that wasn't
+            for (path, line, handle) in handles {
+                match handle.join() {
+                    Ok(_) if self.failed => {}
+                    Ok(decoded) => self.decoded.extend(decoded),
+                    Err(_) if self.failed => {}
+                    Err(_) => {
+                        let e = io::Error::other("the thread decoding the batch starting here panicked");
+                        self.decoded.push_back(Err(ReadError { path, line, kind: ReadErrorKind::Io(e) }));
+                        self.failed = true;
+                    }
+                }
+            }
+        });
+    }
+}
+impl Iterator for ParallelDatasetReader {
+    type Item = Result<Maze, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.decoded.is_empty() && !self.failed {
+            self.fill();
+        }
+        self.decoded.pop_front()
+    }
+}
+
+struct Lines<'a>(&'a mut DatasetReader);
+impl Iterator for Lines<'_> {
+    type Item = Result<RawLine, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_line()
+    }
+}
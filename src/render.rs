@@ -0,0 +1,307 @@
+use std::io::{self, IsTerminal, Write};
+
+use crate::error::{MazeError, Result};
+use crate::maze_logic::Maze;
+
+// box drawing junctions indexed by the walls meeting at a corner,
+// bit 0 up, bit 1 right, bit 2 down, bit 3 left
+const JUNCTIONS: [char; 16] = [
+    ' ', '╵', '╶', '└', '╷', '│', '┌', '├',
+    '╴', '┘', '─', '┴', '┐', '┤', '┬', '┼',
+];
+const UP: usize = 1;
+const RIGHT: usize = 2;
+const DOWN: usize = 4;
+const LEFT: usize = 8;
+
+const RESET: &str = "\x1b[0m";
+const START_COLOR: &str = "\x1b[1;32m";
+const END_COLOR: &str = "\x1b[1;31m";
+const PATH_COLOR: &str = "\x1b[33m";
+const PREDICTED_COLOR: &str = "\x1b[36m";
+const MISSED_COLOR: &str = "\x1b[35m";
+const WRONG_COLOR: &str = "\x1b[31m";
+const VISITED_COLOR: &str = "\x1b[34m";
+const AGENT_COLOR: &str = "\x1b[1;97;44m";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Charset {
+    /// box drawing characters with proper junctions
+    Unicode,
+    /// `+`, `-` and `|`, for logs and terminals without unicode
+    Ascii,
+}
+
+/// What render draws on top of the maze. When both the solution and a
+/// predicted path are shown, cells on both are drawn as the solution, cells
+/// only on the solution as missed, in brackets, and cells only on the
+/// prediction as wrong, with an `x`.
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    pub charset: Charset,
+    /// ANSI colors for start, end, paths, mismatches and visited cells
+    pub color: bool,
+    /// mark the cells of ideal_path
+    pub solution: bool,
+    /// draw paths as arrows in the direction of travel instead of dots
+    pub arrows: bool,
+    /// a path to compare against the solution, as (dx, dy) steps from
+    /// start_pos like ideal_path
+    pub predicted: Option<Vec<(i8, i8)>>,
+    /// one flag per cell, row major, e.g. the cells an agent or solver went through
+    pub visited: Option<Vec<bool>>,
+    /// (x, y) of a player or agent, drawn as `@` over everything else
+    pub agent: Option<(u8, u8)>,
+}
+impl RenderOptions {
+    /// unicode and colors when stdout is a terminal, plain ASCII otherwise
+    pub fn for_stdout() -> Self {
+        if io::stdout().is_terminal() {
+            RenderOptions::default()
+        } else {
+            RenderOptions { charset: Charset::Ascii, color: false, ..RenderOptions::default() }
+        }
+    }
+}
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            charset: Charset::Unicode,
+            color: true,
+            solution: true,
+            arrows: false,
+            predicted: None,
+            visited: None,
+            agent: None,
+        }
+    }
+}
+
+// what a cell shows. path marks carry the step leaving the cell, if any
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Empty,
+    Visited,
+    /// on the solution, and on the prediction if there is one
+    Path(Option<(i8, i8)>),
+    /// on the prediction while the solution isn't shown
+    Predicted(Option<(i8, i8)>),
+    /// on the solution but not the prediction
+    Missed(Option<(i8, i8)>),
+    /// on the prediction but not the solution
+    Wrong,
+    Start,
+    End,
+    Agent,
+}
+impl Mark {
+    fn glyph(self, charset: Charset, arrows: bool) -> char {
+        let unicode = charset == Charset::Unicode;
+        match self {
+            Mark::Empty => ' ',
+            Mark::Visited => if unicode { '·' } else { '.' },
+            Mark::Path(step) | Mark::Predicted(step) | Mark::Missed(step) => match step.filter(|_| arrows) {
+                Some(step) => arrow(step, charset),
+                None => if unicode { '•' } else { 'o' },
+            },
+            Mark::Wrong => if unicode { '×' } else { 'x' },
+            Mark::Start => 'S',
+            Mark::End => 'E',
+            Mark::Agent => '@',
+        }
+    }
+
+    fn color(self) -> Option<&'static str> {
+        match self {
+            Mark::Empty => None,
+            Mark::Visited => Some(VISITED_COLOR),
+            Mark::Path(_) => Some(PATH_COLOR),
+            Mark::Predicted(_) => Some(PREDICTED_COLOR),
+            Mark::Missed(_) => Some(MISSED_COLOR),
+            Mark::Wrong => Some(WRONG_COLOR),
+            Mark::Start => Some(START_COLOR),
+            Mark::End => Some(END_COLOR),
+            Mark::Agent => Some(AGENT_COLOR),
+        }
+    }
+}
+
+// (x, y) of a cell on a path and the step leaving it, None for the last cell
+type TracedCell = ((usize, usize), Option<(i8, i8)>);
+
+fn arrow(step: (i8, i8), charset: Charset) -> char {
+    match (step, charset) {
+        ((-1, 0), Charset::Unicode) => '←',
+        ((1, 0), Charset::Unicode) => '→',
+        ((0, -1), Charset::Unicode) => '↑',
+        ((0, 1), Charset::Unicode) => '↓',
+        ((-1, 0), Charset::Ascii) => '<',
+        ((1, 0), Charset::Ascii) => '>',
+        ((0, -1), Charset::Ascii) => '^',
+        ((0, 1), Charset::Ascii) => 'v',
+        _ => '?',
+    }
+}
+
+impl Maze {
+    /// Renders the maze for a terminal, three characters per cell. See
+    /// RenderOptions for what's shown.
+    pub fn render(&self, options: &RenderOptions) -> Result<String> {
+        self.check_pos(self.start_pos)?;
+        self.check_pos(self.end_pos)?;
+        let cells = self.width * self.height;
+        let mut marks = vec![Mark::Empty; cells];
+        if let Some(visited) = &options.visited {
+            if visited.len() != cells {
+                return Err(MazeError::ShapeMismatch { expected: cells, got: visited.len() });
+            }
+            for (mark, &visited) in marks.iter_mut().zip(visited) {
+                if visited {
+                    *mark = Mark::Visited;
+                }
+            }
+        }
+        let solution = if options.solution { self.trace(&self.ideal_path) } else { vec![] };
+        let predicted = options.predicted.as_ref().map(|path| self.trace(path));
+        let index = |(x, y): (usize, usize)| y * self.width + x;
+        match &predicted {
+            None => {
+                for &(cell, step) in &solution {
+                    marks[index(cell)] = Mark::Path(step);
+                }
+            }
+            Some(predicted) if !options.solution => {
+                for &(cell, step) in predicted {
+                    marks[index(cell)] = Mark::Predicted(step);
+                }
+            }
+            Some(predicted) => {
+                let mut on_prediction = vec![false; cells];
+                for &(cell, _) in predicted {
+                    on_prediction[index(cell)] = true;
+                    marks[index(cell)] = Mark::Wrong;
+                }
+                for &(cell, step) in &solution {
+                    marks[index(cell)] = if on_prediction[index(cell)] { Mark::Path(step) } else { Mark::Missed(step) };
+                }
+            }
+        }
+        marks[self.start_pos.1 as usize * self.width + self.start_pos.0 as usize] = Mark::Start;
+        marks[self.end_pos.1 as usize * self.width + self.end_pos.0 as usize] = Mark::End;
+        if let Some(agent) = options.agent {
+            self.check_pos(agent)?;
+            marks[agent.1 as usize * self.width + agent.0 as usize] = Mark::Agent;
+        }
+
+        let (horizontal, vertical) = match options.charset {
+            Charset::Unicode => ("───", '│'),
+            Charset::Ascii => ("---", '|'),
+        };
+        let mut out = String::new();
+        for y in 0..=self.height {
+            // the line of corners and horizontal walls above row y
+            for x in 0..=self.width {
+                out.push(self.junction(x, y, options.charset));
+                if x < self.width {
+                    out.push_str(if self.wall_above(x, y) { horizontal } else { "   " });
+                }
+            }
+            out.push('\n');
+            if y == self.height {
+                break;
+            }
+
+            for x in 0..=self.width {
+                out.push(if self.wall_left_of(x, y) { vertical } else { ' ' });
+                if x == self.width {
+                    break;
+                }
+                let mark = marks[y * self.width + x];
+                let glyph = mark.glyph(options.charset, options.arrows);
+                let (open, close) = if matches!(mark, Mark::Missed(_)) { ('[', ']') } else { (' ', ' ') };
+                match mark.color().filter(|_| options.color) {
+                    Some(color) => out.push_str(&format!("{}{}{}{}{}", open, color, glyph, RESET, close)),
+                    None => out.push_str(&format!("{}{}{}", open, glyph, close)),
+                }
+            }
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    /// Prints render(RenderOptions::for_stdout()) to stdout
+    pub fn print_pretty(&self) -> Result<()> {
+        self.print_with(&RenderOptions::for_stdout())
+    }
+
+    /// Prints the maze with overlays, e.g. a model's path against the solution:
+    ///
+    /// ```no_run
+    /// # fn main() -> maze::Result<()> {
+    /// # let maze = maze::Maze::new(9, 9)?;
+    /// # let predicted = vec![];
+    /// let options = maze::RenderOptions { arrows: true, predicted: Some(predicted), ..maze::RenderOptions::for_stdout() };
+    /// maze.print_with(&options)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn print_with(&self, options: &RenderOptions) -> Result<()> {
+        let rendered = self.render(options)?;
+        let stdout = io::stdout();
+        let mut handle = stdout.lock();
+        handle.write_all(rendered.as_bytes())?;
+        handle.flush()?;
+        Ok(())
+    }
+
+    // (x, y) of the cells `path` passes through from start_pos, each with the
+    // step leaving it. Walls are ignored, the path is cut where it leaves the maze
+    fn trace(&self, path: &[(i8, i8)]) -> Vec<TracedCell> {
+        let (mut x, mut y) = (self.start_pos.0 as i64, self.start_pos.1 as i64);
+        let mut cells = vec![];
+        for (i, &(dx, dy)) in path.iter().enumerate() {
+            cells.push(((x as usize, y as usize), Some((dx, dy))));
+            x += dx as i64;
+            y += dy as i64;
+            if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+                return cells;
+            }
+            if i == path.len() - 1 {
+                cells.push(((x as usize, y as usize), None));
+            }
+        }
+        cells
+    }
+
+    // wall on the top edge of the cell at (x, y), y == height is the bottom border
+    fn wall_above(&self, x: usize, y: usize) -> bool {
+        y == 0 || y == self.height || self.b_walls[(y - 1) * self.width + x]
+    }
+
+    // wall on the left edge of the cell at (x, y), x == width is the right border
+    fn wall_left_of(&self, x: usize, y: usize) -> bool {
+        x == 0 || x == self.width || self.r_walls[y * self.width + x - 1]
+    }
+
+    // the glyph for grid corner (x, y), the top left corner of cell (x, y)
+    fn junction(&self, x: usize, y: usize, charset: Charset) -> char {
+        let mut walls = 0;
+        if y > 0 && self.wall_left_of(x, y - 1) {
+            walls |= UP;
+        }
+        if y < self.height && self.wall_left_of(x, y) {
+            walls |= DOWN;
+        }
+        if x > 0 && self.wall_above(x - 1, y) {
+            walls |= LEFT;
+        }
+        if x < self.width && self.wall_above(x, y) {
+            walls |= RIGHT;
+        }
+        match charset {
+            Charset::Unicode => JUNCTIONS[walls],
+            Charset::Ascii if walls == 0 => ' ',
+            Charset::Ascii => '+',
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::decode::DecodedPath;
+use crate::error::Result;
+use crate::maze_logic::{Generator, Maze};
+use crate::rollout::EpisodeStats;
+
+// z for a two sided 95% interval
+const Z: f64 = 1.96;
+// upper ends of the solution length buckets, the last bucket is open
+const LENGTH_BUCKETS: [usize; 5] = [5, 10, 20, 40, 80];
+
+/// One evaluated maze, whatever produced the prediction
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct Outcome {
+    pub width: usize,
+    pub height: usize,
+    pub generator: Generator,
+    /// length of the maze's ideal_path
+    pub solution_length: usize,
+    pub success: bool,
+    /// steps taken / ideal steps, for successes where that means something
+    pub length_ratio: Option<f64>,
+}
+impl Outcome {
+    /// a one shot path prediction, successful if it follows the maze from
+    /// start_pos to end_pos
+    pub fn from_prediction(maze: &Maze, predicted: &[(i8, i8)]) -> Self {
+        let mut followed = maze.clone();
+        followed.ideal_path = predicted.to_vec();
+        let success = followed.can_follow_path();
+        Outcome {
+            width: maze.width,
+            height: maze.height,
+            generator: maze.generator,
+            solution_length: maze.ideal_path.len(),
+            success,
+            length_ratio: success.then(|| predicted.len() as f64 / maze.ideal_path.len().max(1) as f64),
+        }
+    }
+
+    pub fn from_decoded(maze: &Maze, decoded: &DecodedPath) -> Self {
+        Outcome::from_prediction(maze, &decoded.offsets())
+    }
+}
+impl From<&EpisodeStats> for Outcome {
+    fn from(episode: &EpisodeStats) -> Self {
+        Outcome {
+            width: episode.width,
+            height: episode.height,
+            generator: episode.generator,
+            solution_length: episode.ideal_steps,
+            success: episode.success,
+            length_ratio: episode.success.then(|| episode.steps as f64 / episode.ideal_steps.max(1) as f64),
+        }
+    }
+}
+
+/// Results for one group of outcomes, with 95% confidence intervals
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GroupStats {
+    pub name: String,
+    pub count: usize,
+    pub successes: usize,
+    pub success_rate: f64,
+    /// Wilson score interval
+    pub success_interval: (f64, f64),
+    /// None when nothing in the group has a length ratio
+    pub mean_length_ratio: Option<f64>,
+    /// normal approximation, None with fewer than two ratios
+    pub length_ratio_interval: Option<(f64, f64)>,
+}
+impl GroupStats {
+    fn new(name: String, outcomes: &[&Outcome]) -> Self {
+        let count = outcomes.len();
+        let successes = outcomes.iter().filter(|outcome| outcome.success).count();
+        let ratios: Vec<f64> = outcomes.iter().filter_map(|outcome| outcome.length_ratio).collect();
+        let mean = (!ratios.is_empty()).then(|| ratios.iter().sum::<f64>() / ratios.len() as f64);
+        let interval = mean.filter(|_| ratios.len() > 1).map(|mean| {
+            let variance = ratios.iter().map(|ratio| (ratio - mean).powi(2)).sum::<f64>() / (ratios.len() - 1) as f64;
+            let margin = Z * (variance / ratios.len() as f64).sqrt();
+            (mean - margin, mean + margin)
+        });
+
+        GroupStats {
+            name,
+            count,
+            successes,
+            success_rate: if count == 0 { 0.0 } else { successes as f64 / count as f64 },
+            success_interval: wilson(successes, count),
+            mean_length_ratio: mean,
+            length_ratio_interval: interval,
+        }
+    }
+}
+
+fn wilson(successes: usize, count: usize) -> (f64, f64) {
+    if count == 0 {
+        return (0.0, 1.0);
+    }
+    let n = count as f64;
+    let p = successes as f64 / n;
+    let denominator = 1.0 + Z * Z / n;
+    let centre = (p + Z * Z / (2.0 * n)) / denominator;
+    let margin = Z * (p * (1.0 - p) / n + Z * Z / (4.0 * n * n)).sqrt() / denominator;
+    ((centre - margin).max(0.0), (centre + margin).min(1.0))
+}
+
+fn length_bucket(length: usize) -> (usize, String) {
+    let mut low = 0;
+    for (i, &high) in LENGTH_BUCKETS.iter().enumerate() {
+        if length < high {
+            return (i, format!("length {}-{}", low, high - 1));
+        }
+        low = high;
+    }
+    (LENGTH_BUCKETS.len(), format!("length {}+", low))
+}
+
+/// Evaluation results overall and broken down by maze size, solution length
+/// and generator
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EvalReport {
+    pub overall: GroupStats,
+    pub by_size: Vec<GroupStats>,
+    pub by_length: Vec<GroupStats>,
+    pub by_generator: Vec<GroupStats>,
+}
+impl EvalReport {
+    pub fn new(outcomes: &[Outcome]) -> Self {
+        // keyed so groups come out ordered by size, length and generator
+        let mut by_size: BTreeMap<(usize, usize), Vec<&Outcome>> = BTreeMap::new();
+        let mut by_length: BTreeMap<usize, (String, Vec<&Outcome>)> = BTreeMap::new();
+        let mut by_generator: BTreeMap<String, Vec<&Outcome>> = BTreeMap::new();
+        for outcome in outcomes {
+            by_size.entry((outcome.width, outcome.height)).or_default().push(outcome);
+            let (bucket, name) = length_bucket(outcome.solution_length);
+            by_length.entry(bucket).or_insert_with(|| (name, vec![])).1.push(outcome);
+            by_generator.entry(format!("{:?}", outcome.generator)).or_default().push(outcome);
+        }
+
+        EvalReport {
+            overall: GroupStats::new("overall".to_string(), &outcomes.iter().collect::<Vec<_>>()),
+            by_size: by_size
+                .into_iter()
+                .map(|((width, height), group)| GroupStats::new(format!("{}x{}", width, height), &group))
+                .collect(),
+            by_length: by_length.into_values().map(|(name, group)| GroupStats::new(name, &group)).collect(),
+            by_generator: by_generator.into_iter().map(|(name, group)| GroupStats::new(name, &group)).collect(),
+        }
+    }
+
+    pub fn from_episodes(episodes: &[EpisodeStats]) -> Self {
+        EvalReport::new(&episodes.iter().map(Outcome::from).collect::<Vec<_>>())
+    }
+
+    pub fn write_json(&self, path: &Path) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writeln!(writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// the report as a plain text table, one row per group
+impl fmt::Display for EvalReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<20} {:>8} {:>8} {:>18} {:>22}", "group", "mazes", "success", "95% ci", "length ratio (95% ci)")?;
+        let sections = [
+            ("", std::slice::from_ref(&self.overall)),
+            ("by size", &self.by_size[..]),
+            ("by solution length", &self.by_length[..]),
+            ("by generator", &self.by_generator[..]),
+        ];
+        for (title, groups) in sections {
+            if !title.is_empty() {
+                writeln!(f, "{}", title)?;
+            }
+            for group in groups {
+                let (low, high) = group.success_interval;
+                let ratio = match (group.mean_length_ratio, group.length_ratio_interval) {
+                    (Some(mean), Some((low, high))) => format!("{:.2} ({:.2}-{:.2})", mean, low, high),
+                    (Some(mean), None) => format!("{:.2}", mean),
+                    (None, _) => "-".to_string(),
+                };
+                writeln!(
+                    f,
+                    "{:<20} {:>8} {:>7.1}% {:>18} {:>22}",
+                    group.name,
+                    group.count,
+                    100.0 * group.success_rate,
+                    format!("{:.1}-{:.1}%", 100.0 * low, 100.0 * high),
+                    ratio,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,254 @@
+use ndarray::{Array3, Axis};
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+use std::path::Path;
+use tract_onnx::prelude::*;
+
+use crate::dataset::{gen_maze, index_rng};
+use crate::env::*;
+use crate::error::{MazeError, Result};
+use crate::maze_logic::{Direction, Generator, Maze};
+use crate::trajectory::{Agent, Trajectory};
+
+type Plan = SimplePlan<TypedFact, Box<dyn TypedOp>, Graph<TypedFact, Box<dyn TypedOp>>>;
+
+/// How a policy's logits become an action
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ActionSelection {
+    /// always the most likely action
+    Argmax,
+    /// sampled from softmax(logits / temperature)
+    Sample { temperature: f32 },
+}
+
+/// An ONNX policy taking a 1 x CHANNELS x rows x columns f32 observation, as
+/// written by MazeEnv::write_observation, and returning 4 logits in
+/// Direction::ALL order.
+pub struct OnnxPolicy {
+    plan: Plan,
+    shape: (usize, usize, usize),
+    model: String,
+}
+impl OnnxPolicy {
+    /// `shape` is the observation shape the model was trained on, usually
+    /// EnvConfig::observation_shape
+    pub fn load(path: &Path, shape: (usize, usize, usize)) -> Result<Self> {
+        let plan = tract_onnx::onnx()
+            .model_for_path(path)?
+            .with_input_fact(0, f32::fact([1, shape.0, shape.1, shape.2]).into())?
+            .into_optimized()?
+            .into_runnable()?;
+        Ok(OnnxPolicy { plan, shape, model: path.display().to_string() })
+    }
+
+    /// who made the moves in trajectories this policy records
+    pub fn agent(&self) -> Agent {
+        Agent::Policy { model: self.model.clone() }
+    }
+
+    pub fn observation_shape(&self) -> (usize, usize, usize) {
+        self.shape
+    }
+
+    /// logits for Left, Right, Up and Down given one observation
+    pub fn logits(&self, observation: &Array3<f32>) -> Result<[f32; 4]> {
+        let input: Tensor = observation.clone().insert_axis(Axis(0)).into();
+        let outputs = self.plan.run(tvec!(input.into()))?;
+        let logits = outputs[0].to_array_view::<f32>()?;
+        let logits: Vec<f32> = logits.iter().copied().collect();
+        logits.try_into().map_err(|logits: Vec<f32>| {
+            MazeError::Model(anyhow::anyhow!("expected 4 logits, got {}", logits.len()))
+        })
+    }
+}
+
+fn select<R: Rng>(logits: [f32; 4], selection: ActionSelection, rng: &mut R) -> Direction {
+    let best = (0..4).fold(0, |best, i| if logits[i] > logits[best] { i } else { best });
+    let ActionSelection::Sample { temperature } = selection else {
+        return Direction::ALL[best];
+    };
+    let weights = logits.map(|logit| ((logit - logits[best]) / temperature).exp());
+    match rand::distributions::WeightedIndex::new(weights) {
+        Ok(distribution) => Direction::ALL[distribution.sample(rng)],
+        Err(_) => Direction::ALL[best],
+    }
+}
+
+/// What happened in a single episode
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct EpisodeStats {
+    pub width: usize,
+    pub height: usize,
+    pub generator: Generator,
+    pub success: bool,
+    pub steps: usize,
+    /// length of the maze's ideal_path
+    pub ideal_steps: usize,
+    pub wall_bumps: usize,
+    /// moves onto a cell the agent had already been on this episode
+    pub revisits: usize,
+}
+
+/// Totals over a set of episodes
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RolloutReport {
+    pub episodes: usize,
+    pub successes: usize,
+    pub success_rate: f64,
+    /// steps / ideal steps, averaged over successful episodes only
+    pub mean_length_ratio: f64,
+    pub mean_wall_bumps: f64,
+    /// episodes with at least one revisit
+    pub looped_episodes: usize,
+    pub mean_revisits: f64,
+}
+impl RolloutReport {
+    pub fn from_episodes(episodes: &[EpisodeStats]) -> Self {
+        let count = episodes.len();
+        let mean = |total: usize, of: usize| if of == 0 { 0.0 } else { total as f64 / of as f64 };
+        let successful: Vec<_> = episodes.iter().filter(|episode| episode.success).collect();
+        let ratios: f64 = successful
+            .iter()
+            .map(|episode| episode.steps as f64 / episode.ideal_steps.max(1) as f64)
+            .sum();
+
+        RolloutReport {
+            episodes: count,
+            successes: successful.len(),
+            success_rate: mean(successful.len(), count),
+            mean_length_ratio: if successful.is_empty() { 0.0 } else { ratios / successful.len() as f64 },
+            mean_wall_bumps: mean(episodes.iter().map(|episode| episode.wall_bumps).sum(), count),
+            looped_episodes: episodes.iter().filter(|episode| episode.revisits > 0).count(),
+            mean_revisits: mean(episodes.iter().map(|episode| episode.revisits).sum(), count),
+        }
+    }
+}
+
+/// Plays one episode on `maze` until the policy reaches the goal or the env
+/// cuts it off. `maze` needs an ideal_path, as every generated or dataset maze has.
+/// Returns ObservationShape if the observation of `maze` doesn't fit the
+/// policy's input, rather than cropping it.
+pub fn run_episode<R: Rng>(
+    policy: &OnnxPolicy,
+    env: &mut MazeEnv,
+    maze: Maze,
+    selection: ActionSelection,
+    rng: &mut R,
+) -> Result<EpisodeStats> {
+    let mut stats = EpisodeStats {
+        width: maze.width,
+        height: maze.height,
+        generator: maze.generator,
+        success: false,
+        steps: 0,
+        ideal_steps: maze.ideal_path.len(),
+        wall_bumps: 0,
+        revisits: 0,
+    };
+    env.reset_with_maze(maze)?;
+    let (channels, rows, columns) = policy.observation_shape();
+    // smaller observations are padded, larger ones would lose part of the maze
+    let needed = env.observation_shape();
+    if needed.0 != channels || needed.1 > rows || needed.2 > columns {
+        return Err(MazeError::ObservationShape { needed, policy: (channels, rows, columns) });
+    }
+    let mut observation = Array3::zeros((channels, rows, columns));
+    let mut visited = HashSet::from([env.position()]);
+
+    loop {
+        env.write_observation(observation.view_mut());
+        let action = select(policy.logits(&observation)?, selection, rng);
+        let (_, done, info) = env.step_only(action)?;
+        stats.steps = info.steps;
+        if info.bumped_wall {
+            stats.wall_bumps += 1;
+        } else if !visited.insert(env.position()) {
+            stats.revisits += 1;
+        }
+        if done {
+            stats.success = info.reached_goal;
+            return Ok(stats);
+        }
+    }
+}
+
+/// Runs `policy` for `episodes` episodes on mazes generated from `seed` by
+/// `config`, the same mazes for the same seed.
+pub fn evaluate_policy(
+    policy: &OnnxPolicy,
+    config: &EnvConfig,
+    episodes: usize,
+    seed: u64,
+    selection: ActionSelection,
+) -> Result<RolloutReport> {
+    Ok(RolloutReport::from_episodes(&rollout_policy(policy, config, episodes, seed, selection)?))
+}
+
+/// Runs `policy` once on each maze, for example the records of a test split
+pub fn evaluate_policy_on<I: IntoIterator<Item = Maze>>(
+    policy: &OnnxPolicy,
+    config: &EnvConfig,
+    mazes: I,
+    seed: u64,
+    selection: ActionSelection,
+) -> Result<RolloutReport> {
+    Ok(RolloutReport::from_episodes(&rollout_policy_on(policy, config, mazes, seed, selection)?))
+}
+
+/// evaluate_policy without the totals, one EpisodeStats per episode
+pub fn rollout_policy(
+    policy: &OnnxPolicy,
+    config: &EnvConfig,
+    episodes: usize,
+    seed: u64,
+    selection: ActionSelection,
+) -> Result<Vec<EpisodeStats>> {
+    Ok(record_policy(policy, config, episodes, seed, selection)?.into_iter().map(|(stats, _)| stats).collect())
+}
+
+/// evaluate_policy_on without the totals, one EpisodeStats per maze
+pub fn rollout_policy_on<I: IntoIterator<Item = Maze>>(
+    policy: &OnnxPolicy,
+    config: &EnvConfig,
+    mazes: I,
+    seed: u64,
+    selection: ActionSelection,
+) -> Result<Vec<EpisodeStats>> {
+    Ok(record_policy_on(policy, config, mazes, seed, selection)?.into_iter().map(|(stats, _)| stats).collect())
+}
+
+/// rollout_policy along with the trajectory of each episode
+pub fn record_policy(
+    policy: &OnnxPolicy,
+    config: &EnvConfig,
+    episodes: usize,
+    seed: u64,
+    selection: ActionSelection,
+) -> Result<Vec<(EpisodeStats, Trajectory)>> {
+    let mazes = (0..episodes)
+        .map(|episode| Ok(gen_maze(&config.mazes, &mut index_rng(seed, episode, 0))?.0))
+        .collect::<Result<Vec<_>>>()?;
+    record_policy_on(policy, config, mazes, seed, selection)
+}
+
+/// rollout_policy_on along with the trajectory of each episode
+pub fn record_policy_on<I: IntoIterator<Item = Maze>>(
+    policy: &OnnxPolicy,
+    config: &EnvConfig,
+    mazes: I,
+    seed: u64,
+    selection: ActionSelection,
+) -> Result<Vec<(EpisodeStats, Trajectory)>> {
+    let mut env = MazeEnv::new(config.clone())?;
+    let mut rng = StdRng::seed_from_u64(seed);
+    mazes
+        .into_iter()
+        .map(|maze| {
+            let stats = run_episode(policy, &mut env, maze, selection, &mut rng)?;
+            let trajectory = env.trajectory(policy.agent()).ok_or(MazeError::EpisodeOver)?;
+            Ok((stats, trajectory))
+        })
+        .collect()
+}
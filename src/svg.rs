@@ -0,0 +1,274 @@
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::error::{MazeError, Result};
+use crate::grid::Grid;
+use crate::maze_logic::{Direction, Maze};
+
+/// Styles the classes to_svg emits, embedded unless SvgOptions::stylesheet is off
+pub const DEFAULT_STYLE: &str = "\
+.maze { background: white; }
+.wall { fill: none; stroke: black; stroke-width: 2; stroke-linecap: square; }
+.heat { fill: crimson; }
+.solution { fill: none; stroke: royalblue; stroke-width: 3; stroke-linejoin: round; stroke-linecap: round; }
+.start { fill: seagreen; }
+.end { fill: darkorange; }";
+
+#[derive(Clone, Debug)]
+pub struct SvgOptions {
+    /// side of a cell in svg units
+    pub cell_size: f32,
+    /// space around the maze
+    pub margin: f32,
+    /// draw ideal_path as a polyline from start_pos
+    pub solution: bool,
+    /// one value in [0, 1] per cell, row major, drawn as the fill opacity of
+    /// the cell
+    pub heatmap: Option<Vec<f32>>,
+    /// embed DEFAULT_STYLE, turn off to style the classes from outside
+    pub stylesheet: bool,
+}
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions { cell_size: 20.0, margin: 10.0, solution: true, heatmap: None, stylesheet: true }
+    }
+}
+
+impl Maze {
+    /// Renders the maze with grid_svg. Every straight run of wall is a single
+    /// `<line class="wall">`, cells of the heatmap are `<path class="heat">`,
+    /// the solution is a `<polyline class="solution">`, and start_pos and
+    /// end_pos are `<circle class="start">` and `<circle class="end">`.
+    pub fn to_svg(&self, options: &SvgOptions) -> Result<String> {
+        grid_svg(self, self.start_pos, self.end_pos, &self.ideal_directions(), options)
+    }
+
+    pub fn write_svg(&self, path: &Path, options: &SvgOptions) -> Result<()> {
+        std::fs::write(path, self.to_svg(options)?)?;
+        Ok(())
+    }
+
+    /// Maximal straight wall runs as pairs of grid corners ((x, y), (x, y)),
+    /// the outer border included. Horizontal runs come first, top to bottom.
+    pub fn wall_runs(&self) -> Vec<((usize, usize), (usize, usize))> {
+        let mut runs = vec![];
+        // wall above the cell at (x, y), y == height is below the last row
+        let horizontal = |x: usize, y: usize| y == 0 || self.b_walls[(y - 1) * self.width + x];
+        for y in 0..=self.height {
+            let mut x = 0;
+            while x < self.width {
+                if !horizontal(x, y) {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < self.width && horizontal(x, y) {
+                    x += 1;
+                }
+                runs.push(((start, y), (x, y)));
+            }
+        }
+
+        // wall left of the cell at (x, y), x == width is right of the last column
+        let vertical = |x: usize, y: usize| x == 0 || self.r_walls[y * self.width + x - 1];
+        for x in 0..=self.width {
+            let mut y = 0;
+            while y < self.height {
+                if !vertical(x, y) {
+                    y += 1;
+                    continue;
+                }
+                let start = y;
+                while y < self.height && vertical(x, y) {
+                    y += 1;
+                }
+                runs.push(((x, start), (x, y)));
+            }
+        }
+        runs
+    }
+}
+
+/// One side of a cell in svg units, margin not included
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Edge {
+    Line { from: (f32, f32), to: (f32, f32) },
+    /// less than half a circle of `radius`, clockwise on screen when `clockwise`
+    Arc { from: (f32, f32), to: (f32, f32), radius: f32, clockwise: bool },
+}
+impl Edge {
+    fn start(self) -> (f32, f32) {
+        match self {
+            Edge::Line { from, .. } | Edge::Arc { from, .. } => from,
+        }
+    }
+
+    // the path command drawing the edge from wherever the path is, shifted by `margin`
+    fn command(self, margin: f32) -> String {
+        match self {
+            Edge::Line { to, .. } => format!("L {} {}", to.0 + margin, to.1 + margin),
+            Edge::Arc { to, radius, clockwise, .. } => {
+                format!("A {} {} 0 0 {} {} {}", radius, radius, clockwise as u8, to.0 + margin, to.1 + margin)
+            }
+        }
+    }
+}
+
+/// Where the cells of a grid go in a drawing, all grid_svg needs to render a shape
+pub trait SvgLayout: Grid {
+    /// (width, height) of the grid, margin not included
+    fn svg_size(&self, cell_size: f32) -> (f32, f32);
+
+    /// where the solution passes through a cell and its marker goes
+    fn svg_centre(&self, pos: Self::Pos, cell_size: f32) -> (f32, f32);
+
+    /// the outline of a cell as one edge per side, each with the direction it
+    /// faces, joined end to end around the cell
+    fn svg_sides(&self, pos: Self::Pos, cell_size: f32) -> Vec<(Self::Dir, Edge)>;
+
+    /// radius of the start and end markers
+    fn svg_marker_radius(&self, cell_size: f32) -> f32 {
+        cell_size * 0.3
+    }
+
+    /// Every wall standing in the grid, each once. The default takes the
+    /// sides of every cell, a wall between two cells drawn by the one with
+    /// the lower index.
+    fn svg_walls(&self, cell_size: f32) -> Vec<Edge> {
+        let mut walls = vec![];
+        for i in 0..self.cell_count() {
+            let pos = self.pos(i);
+            for (direction, edge) in self.svg_sides(pos, cell_size) {
+                let shared_with_lower = self.step(pos, direction).and_then(|next| self.index(next)).is_some_and(|j| j < i);
+                if self.has_wall(pos, direction) && !shared_with_lower {
+                    walls.push(edge);
+                }
+            }
+        }
+        walls
+    }
+}
+
+/// Renders any grid as an SVG document with the classes and stylesheet of
+/// DEFAULT_STYLE. Every wall from SvgLayout::svg_walls is drawn once, as a
+/// `<line class="wall">` or, if it's curved, a `<path class="wall">`. Heatmap values go one per
+/// Grid::index and are drawn as `<path class="heat">` cells, and `path` is
+/// the solution, followed from `start`.
+pub fn grid_svg<G: SvgLayout>(grid: &G, start: G::Pos, end: G::Pos, path: &[G::Dir], options: &SvgOptions) -> Result<String> {
+    let cells = grid.cell_count();
+    if let Some(heatmap) = &options.heatmap {
+        if heatmap.len() != cells {
+            return Err(MazeError::ShapeMismatch { expected: cells, got: heatmap.len() });
+        }
+    }
+
+    let size = options.cell_size;
+    let margin = options.margin;
+    let centre = |pos: G::Pos| {
+        let (x, y) = grid.svg_centre(pos, size);
+        (x + margin, y + margin)
+    };
+
+    let mut svg = String::new();
+    let (width, height) = grid.svg_size(size);
+    let (width, height) = (width + 2.0 * margin, height + 2.0 * margin);
+    // writing to a String can't fail
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" class="maze" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+        width, height, width, height
+    );
+    if options.stylesheet {
+        let _ = writeln!(svg, "<style>\n{}\n</style>", DEFAULT_STYLE);
+    }
+
+    if let Some(heatmap) = &options.heatmap {
+        svg.push_str("<g class=\"heatmap\">\n");
+        for (i, &value) in heatmap.iter().enumerate() {
+            let value = value.clamp(0.0, 1.0);
+            let sides = grid.svg_sides(grid.pos(i), size);
+            let Some((_, first)) = sides.first().filter(|_| value > 0.0) else { continue };
+            let (x, y) = first.start();
+            let mut outline = format!("M {} {}", x + margin, y + margin);
+            for (_, edge) in &sides {
+                outline.push(' ');
+                outline.push_str(&edge.command(margin));
+            }
+            let _ = writeln!(svg, r#"<path class="heat" d="{} Z" fill-opacity="{:.3}"/>"#, outline, value);
+        }
+        svg.push_str("</g>\n");
+    }
+
+    svg.push_str("<g class=\"walls\">\n");
+    for edge in grid.svg_walls(size) {
+        let (x1, y1) = edge.start();
+        match edge {
+            Edge::Line { to: (x2, y2), .. } => {
+                let _ = writeln!(
+                    svg,
+                    r#"<line class="wall" x1="{}" y1="{}" x2="{}" y2="{}"/>"#,
+                    x1 + margin, y1 + margin, x2 + margin, y2 + margin
+                );
+            }
+            Edge::Arc { .. } => {
+                let _ = writeln!(
+                    svg,
+                    r#"<path class="wall" d="M {} {} {}"/>"#,
+                    x1 + margin, y1 + margin, edge.command(margin)
+                );
+            }
+        }
+    }
+    svg.push_str("</g>\n");
+
+    if options.solution && !path.is_empty() && grid.index(start).is_some() {
+        let mut at = start;
+        let mut points = vec![centre(at)];
+        for &direction in path {
+            let Some(next) = grid.step(at, direction) else { break };
+            at = next;
+            points.push(centre(at));
+        }
+        let points: Vec<String> = points.iter().map(|(x, y)| format!("{},{}", x, y)).collect();
+        let _ = writeln!(svg, r#"<polyline class="solution" points="{}"/>"#, points.join(" "));
+    }
+
+    for (class, pos) in [("start", start), ("end", end)] {
+        if grid.index(pos).is_some() {
+            let (x, y) = centre(pos);
+            let _ = writeln!(svg, r#"<circle class="{}" cx="{}" cy="{}" r="{}"/>"#, class, x, y, grid.svg_marker_radius(size));
+        }
+    }
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+impl SvgLayout for Maze {
+    fn svg_size(&self, cell_size: f32) -> (f32, f32) {
+        (self.width as f32 * cell_size, self.height as f32 * cell_size)
+    }
+
+    fn svg_centre(&self, pos: (u8, u8), cell_size: f32) -> (f32, f32) {
+        ((pos.0 as f32 + 0.5) * cell_size, (pos.1 as f32 + 0.5) * cell_size)
+    }
+
+    fn svg_sides(&self, pos: (u8, u8), cell_size: f32) -> Vec<(Direction, Edge)> {
+        let corner = |dx: f32, dy: f32| ((pos.0 as f32 + dx) * cell_size, (pos.1 as f32 + dy) * cell_size);
+        let line = |from, to| Edge::Line { from, to };
+        vec![
+            (Direction::Up, line(corner(0.0, 0.0), corner(1.0, 0.0))),
+            (Direction::Right, line(corner(1.0, 0.0), corner(1.0, 1.0))),
+            (Direction::Down, line(corner(1.0, 1.0), corner(0.0, 1.0))),
+            (Direction::Left, line(corner(0.0, 1.0), corner(0.0, 0.0))),
+        ]
+    }
+
+    // the merged runs of wall_runs rather than one line per cell side
+    fn svg_walls(&self, cell_size: f32) -> Vec<Edge> {
+        let corner = |(x, y): (usize, usize)| (x as f32 * cell_size, y as f32 * cell_size);
+        self.wall_runs()
+            .into_iter()
+            .map(|(from, to)| Edge::Line { from: corner(from), to: corner(to) })
+            .collect()
+    }
+}
@@ -0,0 +1,257 @@
+use crate::maze_logic::Maze;
+
+/// The 8 symmetries of a rectangular grid (the dihedral group D4).
+/// Rotations are clockwise with y pointing down, as in Maze::print.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Symmetry {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+    Transpose,
+    AntiTranspose,
+}
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipVertical,
+        Symmetry::Transpose,
+        Symmetry::AntiTranspose,
+    ];
+
+    /// true when the transform swaps width and height
+    pub fn swaps_axes(self) -> bool {
+        matches!(self, Symmetry::Rotate90 | Symmetry::Rotate270 | Symmetry::Transpose | Symmetry::AntiTranspose)
+    }
+
+    /// (width, height) of a width x height grid after the transform
+    pub fn dims(self, width: usize, height: usize) -> (usize, usize) {
+        if self.swaps_axes() { (height, width) } else { (width, height) }
+    }
+
+    /// maps cell (x, y) of a width x height grid to its position after the transform
+    pub fn map_pos(self, (x, y): (usize, usize), width: usize, height: usize) -> (usize, usize) {
+        match self {
+            Symmetry::Identity => (x, y),
+            Symmetry::Rotate90 => (height - 1 - y, x),
+            Symmetry::Rotate180 => (width - 1 - x, height - 1 - y),
+            Symmetry::Rotate270 => (y, width - 1 - x),
+            Symmetry::FlipHorizontal => (width - 1 - x, y),
+            Symmetry::FlipVertical => (x, height - 1 - y),
+            Symmetry::Transpose => (y, x),
+            Symmetry::AntiTranspose => (height - 1 - y, width - 1 - x),
+        }
+    }
+
+    /// maps a step such as an ideal_path entry, e.g. Rotate90 turns (1, 0) into (0, 1)
+    pub fn map_dir(self, (dx, dy): (i8, i8)) -> (i8, i8) {
+        match self {
+            Symmetry::Identity => (dx, dy),
+            Symmetry::Rotate90 => (-dy, dx),
+            Symmetry::Rotate180 => (-dx, -dy),
+            Symmetry::Rotate270 => (dy, -dx),
+            Symmetry::FlipHorizontal => (-dx, dy),
+            Symmetry::FlipVertical => (dx, -dy),
+            Symmetry::Transpose => (dy, dx),
+            Symmetry::AntiTranspose => (-dy, -dx),
+        }
+    }
+}
+
+// FNV-1a, used instead of DefaultHasher so hashes stay the same across
+// rust versions and can be stored next to a dataset
+struct Fnv64(u64);
+impl Fnv64 {
+    fn new() -> Self {
+        Fnv64(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write(&(value as u64).to_le_bytes());
+    }
+}
+
+impl Maze {
+    // r_walls and b_walls as they would be after applying `symmetry`
+    fn walls_after(&self, symmetry: Symmetry) -> (Vec<bool>, Vec<bool>) {
+        let (new_width, new_height) = symmetry.dims(self.width, self.height);
+        let mut r_walls = vec![true; new_width * new_height];
+        let mut b_walls = vec![true; new_width * new_height];
+
+        // every open passage in the original maps to an open passage between
+        // the two transformed cells, whichever side of the new cell it ends up on
+        let mut open = |a: (usize, usize), b: (usize, usize)| {
+            let a = symmetry.map_pos(a, self.width, self.height);
+            let b = symmetry.map_pos(b, self.width, self.height);
+            let (first, second) = if (a.1, a.0) < (b.1, b.0) { (a, b) } else { (b, a) };
+            if first.1 == second.1 {
+                r_walls[first.1 * new_width + first.0] = false;
+            } else {
+                b_walls[first.1 * new_width + first.0] = false;
+            }
+        };
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let index = y * self.width + x;
+                if x + 1 < self.width && !self.r_walls[index] {
+                    open((x, y), (x + 1, y));
+                }
+                if y + 1 < self.height && !self.b_walls[index] {
+                    open((x, y), (x, y + 1));
+                }
+            }
+        }
+
+        (r_walls, b_walls)
+    }
+
+    /// returns a copy of the maze with `symmetry` applied to the walls, cells,
+    /// start and end positions and ideal_path. width and height swap for
+    /// rotations by 90/270 and the transposes
+    pub fn transform(&self, symmetry: Symmetry) -> Maze {
+        let (width, height) = symmetry.dims(self.width, self.height);
+        let (r_walls, b_walls) = self.walls_after(symmetry);
+
+        let mut cells = vec![false; width * height];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (new_x, new_y) = symmetry.map_pos((x, y), self.width, self.height);
+                cells[new_y * width + new_x] = self.cells[y * self.width + x];
+            }
+        }
+
+        let map_pos = |pos: (u8, u8)| {
+            let (x, y) = symmetry.map_pos((pos.0 as usize, pos.1 as usize), self.width, self.height);
+            (x as u8, y as u8)
+        };
+
+        Maze {
+            width,
+            height,
+            cells,
+            r_walls,
+            b_walls,
+            ideal_path: self.ideal_path.iter().map(|&step| symmetry.map_dir(step)).collect(),
+            start_pos: map_pos(self.start_pos),
+            end_pos: map_pos(self.end_pos),
+            generator: self.generator,
+        }
+    }
+
+    /// all 8 transforms of the maze, starting with the maze itself. mazes that
+    /// are symmetric themselves produce some identical copies
+    pub fn augmentations(&self) -> Vec<Maze> {
+        Symmetry::ALL.iter().map(|&symmetry| self.transform(symmetry)).collect()
+    }
+
+    fn hash_under(&self, symmetry: Symmetry, with_positions: bool) -> u64 {
+        let (width, height) = symmetry.dims(self.width, self.height);
+        let (r_walls, b_walls) = self.walls_after(symmetry);
+
+        let mut hasher = Fnv64::new();
+        hasher.write_usize(width);
+        hasher.write_usize(height);
+        hasher.write(&r_walls.iter().map(|&wall| wall as u8).collect::<Vec<u8>>());
+        hasher.write(&b_walls.iter().map(|&wall| wall as u8).collect::<Vec<u8>>());
+
+        if with_positions {
+            for pos in [self.start_pos, self.end_pos] {
+                let (x, y) = symmetry.map_pos((pos.0 as usize, pos.1 as usize), self.width, self.height);
+                hasher.write_usize(x);
+                hasher.write_usize(y);
+            }
+        }
+
+        hasher.0
+    }
+
+    fn canonical_hash(&self, symmetric: bool, with_positions: bool) -> u64 {
+        if symmetric {
+            Symmetry::ALL
+                .iter()
+                .map(|&symmetry| self.hash_under(symmetry, with_positions))
+                .min()
+                .unwrap()
+        } else {
+            self.hash_under(Symmetry::Identity, with_positions)
+        }
+    }
+
+    /// hash of width, height and the wall layout only. with `symmetric` every
+    /// rotation/reflection of the same maze hashes to the same value
+    pub fn layout_hash(&self, symmetric: bool) -> u64 {
+        self.canonical_hash(symmetric, false)
+    }
+
+    /// like layout_hash but also covers start_pos and end_pos, so it identifies
+    /// a (maze, start, end) task
+    pub fn task_hash(&self, symmetric: bool) -> u64 {
+        self.canonical_hash(symmetric, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::maze_logic::Generator;
+
+    fn solved(width: usize, height: usize, seed: u64) -> Maze {
+        let mut maze = Maze::generate_with(width, height, Generator::RecursiveBacktrack, &mut StdRng::seed_from_u64(seed)).unwrap();
+        maze.bfs_solve().unwrap();
+        maze
+    }
+
+    #[test]
+    fn transforms_keep_the_maze_valid() {
+        let maze = solved(7, 4, 1);
+        for symmetry in Symmetry::ALL {
+            let copy = maze.transform(symmetry);
+            assert_eq!((copy.width, copy.height), symmetry.dims(maze.width, maze.height));
+            // the outer border stays closed
+            for y in 0..copy.height {
+                assert!(copy.r_walls[y * copy.width + copy.width - 1], "{:?}", symmetry);
+            }
+            for x in 0..copy.width {
+                assert!(copy.b_walls[(copy.height - 1) * copy.width + x], "{:?}", symmetry);
+            }
+            assert!(copy.can_follow_path(), "{:?}", symmetry);
+            let mut resolved = copy.clone();
+            assert_eq!(resolved.bfs_solve().unwrap().len(), maze.ideal_path.len());
+        }
+    }
+
+    #[test]
+    fn symmetric_hashes_ignore_transforms() {
+        let maze = solved(7, 4, 2);
+        for symmetry in Symmetry::ALL {
+            let copy = maze.transform(symmetry);
+            assert_eq!(copy.layout_hash(true), maze.layout_hash(true), "{:?}", symmetry);
+            assert_eq!(copy.task_hash(true), maze.task_hash(true), "{:?}", symmetry);
+        }
+    }
+
+    #[test]
+    fn transforms_compose_back_to_the_identity() {
+        let maze = solved(5, 3, 3);
+        let back = maze.transform(Symmetry::Rotate90).transform(Symmetry::Rotate270);
+        assert_eq!(back.task_hash(false), maze.task_hash(false));
+        assert_eq!(back.ideal_path, maze.ideal_path);
+    }
+}
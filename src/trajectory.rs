@@ -0,0 +1,227 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::{cursor, queue, terminal};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{MazeError, Result};
+use crate::game::RawTerminal;
+use crate::maze_logic::{Direction, Generator, Maze};
+use crate::reader::{open_decompressed, DatasetReader};
+use crate::render::RenderOptions;
+use crate::rollout::EpisodeStats;
+
+/// Who or what made the moves
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Agent {
+    Human,
+    /// the shortest path bfs_solve finds
+    Bfs,
+    /// an ONNX policy, by the path it was loaded from
+    Policy { model: String },
+    /// anything else driving MazeEnv
+    Other(String),
+}
+
+/// The maze a trajectory was played on
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum MazeSource {
+    /// the whole maze, so the trajectory stands on its own
+    Embedded(Maze),
+    /// record `index`, counting from 0, of a dataset file or sharded directory
+    Dataset { path: PathBuf, index: usize },
+    /// Maze::generate_with on a StdRng seeded with `seed`, as the game does
+    Seeded { width: usize, height: usize, generator: Generator, seed: u64 },
+}
+impl MazeSource {
+    /// The maze, solved so its ideal_path is there to compare against
+    pub fn resolve(&self) -> Result<Maze> {
+        let mut maze = match self {
+            MazeSource::Embedded(maze) => maze.clone(),
+            MazeSource::Dataset { path, index } => {
+                let path = path.to_string_lossy();
+                match DatasetReader::open(&path)?.skip_records(*index).next() {
+                    Some(maze) => maze?,
+                    None => return Err(MazeError::InvalidConfig(format!("{} has no record {}", path, index))),
+                }
+            }
+            MazeSource::Seeded { width, height, generator, seed } => {
+                Maze::generate_with(*width, *height, *generator, &mut StdRng::seed_from_u64(*seed))?
+            }
+        };
+        if maze.ideal_path.is_empty() {
+            maze.bfs_solve()?;
+        }
+        Ok(maze)
+    }
+}
+
+/// How a trajectory ended
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Ending {
+    /// reached end_pos
+    Solved,
+    /// cut off by a step limit
+    Truncated,
+    /// stopped before reaching end_pos, e.g. a player restarting or quitting
+    Abandoned,
+}
+
+/// Every move made on one maze from start_pos, in the same form whether a
+/// human, BFS or a model made them, so they can be compared directly. Wall
+/// bumps are moves too, they just don't change the position.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Trajectory {
+    pub agent: Agent,
+    pub maze: MazeSource,
+    pub actions: Vec<Direction>,
+    /// milliseconds from the start to each action, empty when the agent
+    /// wasn't timed
+    #[serde(default)]
+    pub times_ms: Vec<u64>,
+    pub ending: Ending,
+    /// hints the player asked for, only the game gives them
+    #[serde(default)]
+    pub hints: usize,
+}
+impl Trajectory {
+    /// The shortest path bfs_solve finds on the maze, the baseline to compare against
+    pub fn bfs(source: MazeSource) -> Result<Self> {
+        let mut maze = source.resolve()?;
+        let actions = maze.bfs_solve()?;
+        Ok(Trajectory { agent: Agent::Bfs, maze: source, actions, times_ms: vec![], ending: Ending::Solved, hints: 0 })
+    }
+
+    /// Where the agent stood before the first action and after each one,
+    /// following the walls of `maze`
+    pub fn positions(&self, maze: &Maze) -> Vec<(u8, u8)> {
+        let mut position = maze.start_pos;
+        let mut positions = vec![position];
+        for &direction in &self.actions {
+            position = maze.neighbour(position, direction).unwrap_or(position);
+            positions.push(position);
+        }
+        positions
+    }
+
+    /// The trajectory as the episode statistics rollouts report, counting
+    /// bumps and revisits the same way, so human, BFS and model play can go
+    /// into the same EvalReport
+    pub fn stats(&self, maze: &Maze) -> EpisodeStats {
+        let mut stats = EpisodeStats {
+            width: maze.width,
+            height: maze.height,
+            generator: maze.generator,
+            success: self.ending == Ending::Solved,
+            steps: self.actions.len(),
+            ideal_steps: maze.ideal_path.len(),
+            wall_bumps: 0,
+            revisits: 0,
+        };
+        let mut visited = HashSet::from([maze.start_pos]);
+        let mut position = maze.start_pos;
+        for &direction in &self.actions {
+            match maze.neighbour(position, direction) {
+                Some(next) => {
+                    position = next;
+                    if !visited.insert(position) {
+                        stats.revisits += 1;
+                    }
+                }
+                None => stats.wall_bumps += 1,
+            }
+        }
+        stats
+    }
+}
+
+/// Writes one JSON trajectory per line
+pub fn write_trajectories(path: &Path, trajectories: &[Trajectory]) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for trajectory in trajectories {
+        serde_json::to_writer(&mut writer, trajectory)?;
+        writeln!(writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads what write_trajectories writes, plain or compressed, skipping blank lines
+pub fn read_trajectories(path: &Path) -> Result<Vec<Trajectory>> {
+    let mut trajectories = vec![];
+    for line in open_decompressed(path)?.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            trajectories.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(trajectories)
+}
+
+fn draw(out: &mut impl Write, trajectory: &Trajectory, maze: &Maze, options: &RenderOptions, step: usize) -> Result<()> {
+    let positions = trajectory.positions(maze);
+    let mut visited = vec![false; maze.width * maze.height];
+    for &(x, y) in &positions[..=step] {
+        visited[y as usize * maze.width + x as usize] = true;
+    }
+    let rendered = maze.render(&RenderOptions {
+        visited: Some(visited),
+        agent: Some(positions[step]),
+        ..options.clone()
+    })?;
+
+    queue!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
+    out.write_all(rendered.replace('\n', "\r\n").as_bytes())?;
+    let last = match step.checked_sub(1).map(|i| trajectory.actions[i]) {
+        Some(direction) if positions[step] == positions[step - 1] => format!("{:?} into a wall", direction),
+        Some(direction) => format!("{:?}", direction),
+        None => "start".to_string(),
+    };
+    write!(
+        out,
+        "{:?}, {:?}  step {} of {}  shortest {}  {}\r\n",
+        trajectory.agent,
+        trajectory.ending,
+        step,
+        trajectory.actions.len(),
+        maze.ideal_path.len(),
+        last
+    )?;
+    write!(out, "right/space next, left back, home/end jump, s solution, q quit\r\n")?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Steps through `trajectory` on `maze` in the terminal, one action per key
+/// press, with the cells it went through so far marked as visited
+pub fn replay(trajectory: &Trajectory, maze: &Maze, options: &RenderOptions) -> Result<()> {
+    let _terminal = RawTerminal::enter()?;
+    let mut stdout = io::stdout();
+    let mut options = options.clone();
+    let mut step = 0;
+    loop {
+        draw(&mut stdout, trajectory, maze, &options, step)?;
+        let Event::Key(key) = event::read()? else { continue };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Right | KeyCode::Char(' ') | KeyCode::Char('l') => step = (step + 1).min(trajectory.actions.len()),
+            KeyCode::Left | KeyCode::Char('h') => step = step.saturating_sub(1),
+            KeyCode::Home => step = 0,
+            KeyCode::End => step = trajectory.actions.len(),
+            KeyCode::Char('s') => options.solution = !options.solution,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use ndarray::{Array4, ArrayView4, ArrayViewMut4, Axis};
+use rand::rngs::StdRng;
+use rand::RngCore;
+use std::thread;
+
+use crate::dataset::index_rng;
+use crate::env::*;
+use crate::error::{MazeError, Result};
+use crate::maze_logic::Direction;
+
+// fewest envs a thread steps per call, a step takes about a microsecond so
+// smaller batches would spend longer spawning the thread than stepping
+const MIN_ENVS_PER_THREAD: usize = 256;
+
+// one environment of the batch and the rng that seeds its episodes
+struct Slot {
+    env: MazeEnv,
+    rng: StdRng,
+}
+impl Slot {
+    fn reset(&mut self) -> Result<()> {
+        self.env.start(self.rng.next_u64())
+    }
+}
+
+/// N independent MazeEnvs stepped together. Observations of every env live in
+/// one preallocated N x CHANNELS x rows x columns buffer that is overwritten
+/// in place on every step.
+///
+/// Finished episodes are reset straight away: after a step that ends an
+/// episode, dones and infos describe the step that finished it while the
+/// observation is already the first one of the next episode.
+///
+/// Full observations are padded to the largest maze config.mazes can produce,
+/// the padding is marked as outside the maze.
+pub struct VecMazeEnv {
+    slots: Vec<Slot>,
+    threads: usize,
+    observations: Array4<f32>,
+    rewards: Vec<f32>,
+    dones: Vec<bool>,
+    infos: Vec<StepInfo>,
+}
+impl VecMazeEnv {
+    /// `num_envs` environments whose episodes are all determined by `seed`
+    pub fn new(config: EnvConfig, num_envs: usize, seed: u64) -> Result<Self> {
+        if num_envs == 0 {
+            return Err(MazeError::InvalidConfig("a VecMazeEnv needs at least one env".to_string()));
+        }
+        let shape = config.observation_shape();
+
+        let slots = (0..num_envs)
+            .map(|index| Ok(Slot { env: MazeEnv::new(config.clone())?, rng: index_rng(seed, index, 0) }))
+            .collect::<Result<Vec<_>>>()?;
+        let mut env = VecMazeEnv {
+            slots,
+            threads: num_cpus::get(),
+            observations: Array4::zeros((num_envs, shape.0, shape.1, shape.2)),
+            rewards: vec![0.0; num_envs],
+            dones: vec![false; num_envs],
+            infos: vec![StepInfo::default(); num_envs],
+        };
+        env.reset()?;
+        Ok(env)
+    }
+
+    /// steps on at most `threads` threads, 1 steps on the calling thread.
+    /// Each thread gets at least MIN_ENVS_PER_THREAD envs, so a batch of up to
+    /// that many always steps on the calling thread
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    pub fn num_envs(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Starts a new episode in every env
+    pub fn reset(&mut self) -> Result<()> {
+        for ((slot, obs), info) in self.slots.iter_mut().zip(self.observations.outer_iter_mut()).zip(&mut self.infos) {
+            slot.reset()?;
+            slot.env.write_observation(obs);
+            *info = StepInfo::default();
+        }
+        self.rewards.fill(0.0);
+        self.dones.fill(false);
+        Ok(())
+    }
+
+    /// Steps env i with actions[i], results are in observations, rewards,
+    /// dones and infos
+    pub fn step(&mut self, actions: &[Direction]) -> Result<()> {
+        if actions.len() != self.slots.len() {
+            return Err(MazeError::InvalidConfig(format!(
+                "{} actions for {} envs", actions.len(), self.slots.len()
+            )));
+        }
+
+        let chunk = self.chunk_size();
+        let inline = self.steps_inline();
+        let batches = self.slots.chunks_mut(chunk)
+            .zip(actions.chunks(chunk))
+            .zip(self.observations.axis_chunks_iter_mut(Axis(0), chunk))
+            .zip(self.rewards.chunks_mut(chunk))
+            .zip(self.dones.chunks_mut(chunk))
+            .zip(self.infos.chunks_mut(chunk));
+
+        if inline {
+            for (((((slots, actions), obs), rewards), dones), infos) in batches {
+                step_batch(slots, actions, obs, rewards, dones, infos)?;
+            }
+            return Ok(());
+        }
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = batches
+                .map(|(((((slots, actions), obs), rewards), dones), infos)| {
+                    scope.spawn(move || step_batch(slots, actions, obs, rewards, dones, infos))
+                })
+                .collect();
+            for handle in handles {
+                handle.join().map_err(|_| MazeError::WorkerPanicked)??;
+            }
+            Ok(())
+        })
+    }
+
+    // envs stepped by each thread
+    fn chunk_size(&self) -> usize {
+        self.slots.len().div_ceil(self.threads).max(MIN_ENVS_PER_THREAD)
+    }
+
+    // a single batch isn't worth a thread
+    fn steps_inline(&self) -> bool {
+        self.chunk_size() >= self.slots.len()
+    }
+
+    pub fn observations(&self) -> ArrayView4<'_, f32> {
+        self.observations.view()
+    }
+
+    pub fn rewards(&self) -> &[f32] {
+        &self.rewards
+    }
+
+    pub fn dones(&self) -> &[bool] {
+        &self.dones
+    }
+
+    pub fn infos(&self) -> &[StepInfo] {
+        &self.infos
+    }
+
+    /// the env at `index`, e.g. to look at its maze
+    pub fn env(&self, index: usize) -> &MazeEnv {
+        &self.slots[index].env
+    }
+}
+
+fn step_batch(
+    slots: &mut [Slot],
+    actions: &[Direction],
+    mut observations: ArrayViewMut4<f32>,
+    rewards: &mut [f32],
+    dones: &mut [bool],
+    infos: &mut [StepInfo],
+) -> Result<()> {
+    for (i, (slot, &action)) in slots.iter_mut().zip(actions).enumerate() {
+        let (reward, done, info) = slot.env.step_only(action)?;
+        if done {
+            slot.reset()?;
+        }
+        slot.env.write_observation(observations.index_axis_mut(Axis(0), i));
+        rewards[i] = reward;
+        dones[i] = done;
+        infos[i] = info;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_batches_step_inline() {
+        let env = VecMazeEnv::new(EnvConfig::default(), 8, 0).unwrap();
+        assert!(env.steps_inline());
+        let env = env.with_threads(1);
+        assert!(env.steps_inline());
+    }
+
+    #[test]
+    fn large_batches_split_across_threads() {
+        let env = VecMazeEnv::new(EnvConfig::default(), 2 * MIN_ENVS_PER_THREAD, 0).unwrap().with_threads(2);
+        assert!(!env.steps_inline());
+        assert!(env.with_threads(1).steps_inline());
+    }
+
+    #[test]
+    fn step_fills_every_env() {
+        let mut env = VecMazeEnv::new(EnvConfig::default(), 8, 0).unwrap().with_threads(1);
+        env.step(&[Direction::Right; 8]).unwrap();
+        assert!(env.infos().iter().all(|info| info.steps == 1));
+    }
+}